@@ -0,0 +1,79 @@
+//!
+//! Dictionary wrapper that keeps a log of rejected inserts.
+//!
+
+use { Dict, DictIface, hash_f };
+
+/// Why an add() was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectReason {
+    /// The key already exists.
+    Duplicate,
+    /// A different key with the same hash already exists.
+    Collision,
+}
+
+/// Dict wrapper that records every rejected add(), so that silently dropped data can be traced.
+pub struct AuditingDict<T> { dict : Dict<T>, rejected : Vec<(String, RejectReason)> }
+
+impl<T> AuditingDict<T> {
+    /// Create an empty dictionary with an empty log.
+    pub fn new() -> AuditingDict<T> {
+        AuditingDict{ dict: Dict::new(), rejected: Vec::new() }
+    }
+    /// Add an element _val_ of type T, indexed by the string _key_. Returns false and logs the key
+    /// if the key exists or there is a hash collision
+    pub fn add( &mut self, key : String, val : T ) -> bool {
+        let reason = match self.dict.binary_search_by_key( &hash_f( &key ), |o| o.hash ) {
+            Ok ( pos ) if self.dict[pos].key == key => RejectReason::Duplicate,
+            Ok (  _  )                               => RejectReason::Collision,
+            Err(  _  )                               => return self.dict.add( key, val ),
+        };
+        self.rejected.push( ( key, reason ) );
+        false
+    }
+    /// Return the keys rejected so far, with the reason, in the order they were rejected.
+    pub fn rejected( &self ) -> &[(String, RejectReason)] {
+        &self.rejected
+    }
+    /// Return a reference to the underlying dictionary.
+    pub fn dict( &self ) -> &Dict<T> {
+        &self.dict
+    }
+    /// Consume the wrapper and return the underlying dictionary, dropping the log.
+    pub fn into_dict( self ) -> Dict<T> {
+        self.dict
+    }
+}
+
+impl<T> Default for AuditingDict<T> {
+    fn default() -> AuditingDict<T> {
+        AuditingDict::new()
+    }
+}
+
+impl<T> From<Dict<T>> for AuditingDict<T> {
+    fn from( dict : Dict<T> ) -> AuditingDict<T> {
+        AuditingDict{ dict, rejected: Vec::new() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use DictEntry;
+    use super::*;
+
+    #[test]
+    fn logs_duplicates_and_collisions() {
+        // forge an element holding the hash of "b" under another key
+        let forged   = vec![ DictEntry{ hash: hash_f( "b" ), key: "not b".to_string(), val: 0 } ];
+        let mut dict = AuditingDict::from( forged );
+        assert!( dict.add( "a".to_string(), 1 ) );
+        assert!( !dict.add( "a".to_string(), 2 ) );
+        assert!( !dict.add( "b".to_string(), 3 ) );
+        assert_eq!( dict.rejected(), &[ ( "a".to_string(), RejectReason::Duplicate ),
+                                        ( "b".to_string(), RejectReason::Collision ) ] );
+        assert_eq!( dict.dict().get( "a" ), Some( &1 ) );
+        assert_eq!( dict.into_dict().len(), 2 );
+    }
+}
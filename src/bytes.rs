@@ -0,0 +1,68 @@
+//!
+//! Compact binary format for dictionaries.
+//!
+//! A dictionary is written as its number of elements, followed by the key and the value of each
+//! element, each one prefixed by its length in bytes. All integers are little endian u64.
+//!
+
+use std::io::{ self, Read, Write };
+
+/// Values that can be written by DictIface::write_to()
+pub trait AsBytes {
+    fn to_bytes( &self ) -> Vec<u8>;
+}
+
+/// Values that can be read by DictIface::read_from()
+pub trait FromBytes: Sized {
+    fn from_bytes( bytes : Vec<u8> ) -> io::Result<Self>;
+}
+
+impl AsBytes for String {
+    fn to_bytes( &self ) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl FromBytes for String {
+    fn from_bytes( bytes : Vec<u8> ) -> io::Result<String> {
+        String::from_utf8( bytes ).map_err( |e| io::Error::new( io::ErrorKind::InvalidData, e ) )
+    }
+}
+
+impl AsBytes for Vec<u8> {
+    fn to_bytes( &self ) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl FromBytes for Vec<u8> {
+    fn from_bytes( bytes : Vec<u8> ) -> io::Result<Vec<u8>> {
+        Ok( bytes )
+    }
+}
+
+pub(crate) fn write_u64<W: Write>( w : &mut W, n : u64 ) -> io::Result<()> {
+    w.write_all( &n.to_le_bytes() )
+}
+
+pub(crate) fn read_u64<R: Read>( r : &mut R ) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact( &mut buf )?;
+    Ok( u64::from_le_bytes( buf ) )
+}
+
+pub(crate) fn write_chunk<W: Write>( w : &mut W, bytes : &[u8] ) -> io::Result<()> {
+    write_u64( w, bytes.len() as u64 )?;
+    w.write_all( bytes )
+}
+
+pub(crate) fn read_chunk<R: Read>( r : &mut R ) -> io::Result<Vec<u8>> {
+    let len = read_u64( r )?;
+    let mut bytes = Vec::new();
+    // don't trust the length to preallocate, the input might be corrupt
+    r.by_ref().take( len ).read_to_end( &mut bytes )?;
+    if ( bytes.len() as u64 ) < len {
+        return Err( io::Error::new( io::ErrorKind::UnexpectedEof, "truncated dict entry" ) );
+    }
+    Ok( bytes )
+}
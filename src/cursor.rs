@@ -0,0 +1,95 @@
+//!
+//! Cursor over the elements of a Dict, in the spirit of
+//! [std::collections::linked_list::CursorMut](https://doc.rust-lang.org/std/collections/linked_list/struct.CursorMut.html)
+//!
+
+use { Dict, debug_check_invariant };
+
+/// Cursor walking a Dict in hash order, able to modify values and remove elements. Past the last
+/// element there is a "ghost" position that wraps around to the first one, and the other way.
+pub struct DictCursor<'a, T> { dict : &'a mut Dict<T>, index : usize }
+
+impl<'a, T> DictCursor<'a, T> {
+    pub(crate) fn new( dict : &'a mut Dict<T> ) -> DictCursor<'a, T> {
+        DictCursor{ dict, index: 0 }
+    }
+    /// Return the position of the cursor, or None if it is at the ghost position.
+    pub fn index( &self ) -> Option<usize> {
+        if self.index < self.dict.len() { Some( self.index ) } else { None }
+    }
+    /// Move to the next element, or to the first one from the ghost position.
+    pub fn move_next( &mut self ) {
+        if self.index < self.dict.len() { self.index += 1 } else { self.index = 0 }
+    }
+    /// Move to the previous element, or to the last one from the ghost position.
+    pub fn move_prev( &mut self ) {
+        if self.index == 0 { self.index = self.dict.len() } else { self.index -= 1 }
+    }
+    /// Return the key of the current element, if any.
+    pub fn key( &self ) -> Option<&str> {
+        self.dict.get( self.index ).map( |o| o.key.as_str() )
+    }
+    /// Return the key and a mutable reference to the value of the current element, if any.
+    pub fn current( &mut self ) -> Option<(&str, &mut T)> {
+        self.dict.get_mut( self.index ).map( |o| ( o.key.as_str(), &mut o.val ) )
+    }
+    /// Remove the current element and return it as a key/value pair, if any. The cursor then
+    /// points to the element that followed it.
+    pub fn remove_current( &mut self ) -> Option<(String, T)> {
+        if self.index >= self.dict.len() {
+            return None;
+        }
+        let entry = self.dict.remove( self.index );
+        debug_check_invariant( self.dict );
+        Some( ( entry.key, entry.val ) )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use DictIface;
+    use super::*;
+
+    #[test]
+    fn walk_mutate_and_remove() {
+        let mut dict = Dict::<i32>::new();
+        for &( key, val ) in &[ ( "a", 1 ), ( "b", 2 ), ( "c", 3 ), ( "d", 4 ) ] {
+            dict.add( key.to_string(), val );
+        }
+        let order : Vec<String> = dict.iter().map( |o| o.key.clone() ).filter( |k| k != "b" ).collect();
+        {
+            let mut cursor = dict.cursor_mut();
+            let mut seen   = Vec::new();
+            while let Some( key ) = cursor.key().map( |k| k.to_string() ) {
+                if key == "b" {
+                    assert_eq!( cursor.remove_current(), Some( ( "b".to_string(), 2 ) ) );
+                    continue;
+                }
+                *cursor.current().unwrap().1 *= 10;
+                seen.push( key );
+                cursor.move_next();
+            }
+            assert_eq!( seen, order );
+            assert_eq!( cursor.index(), None );
+            assert_eq!( cursor.remove_current(), None );
+        }
+        assert_eq!( dict.len(), 3 );
+        assert!( !dict.contains_key( "b" ) );
+        assert_eq!( dict.get( "d" ), Some( &40 ) );
+    }
+
+    #[test]
+    fn ghost_position_wraps_around() {
+        let mut dict = Dict::<i32>::new();
+        dict.add( "a".to_string(), 1 );
+        dict.add( "b".to_string(), 2 );
+        let mut cursor = dict.cursor_mut();
+        cursor.move_prev();
+        assert_eq!( cursor.index(), None );
+        cursor.move_prev();
+        assert_eq!( cursor.index(), Some( 1 ) );
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!( cursor.index(), Some( 0 ) );
+    }
+}
@@ -0,0 +1,125 @@
+//!
+//! Entry API for in-place manipulation of a single element, in the spirit of
+//! [std::collections::hash_map::Entry](https://doc.rust-lang.org/std/collections/hash_map/enum.Entry.html)
+//!
+
+use { Dict, DictEntry, debug_check_invariant };
+
+/// A view into a single element of a Dict, which may be occupied or vacant.
+pub enum Entry<'a, T> {
+    Occupied( OccupiedEntry<'a, T> ),
+    Vacant  ( VacantEntry<'a, T>   ),
+}
+
+/// A view into an occupied element of a Dict.
+pub struct OccupiedEntry<'a, T> { dict : &'a mut Dict<T>, pos : usize }
+
+/// A view into a vacant element of a Dict.
+pub struct VacantEntry<'a, T> { dict : &'a mut Dict<T>, pos : usize, hash : u64, key : String }
+
+impl<'a, T> Entry<'a, T> {
+    pub(crate) fn new( dict : &'a mut Dict<T>, hash : u64, key : String ) -> Entry<'a, T> {
+        match dict.binary_search_by_key( &hash, |o| o.hash ) {
+            Ok ( pos ) => Entry::Occupied( OccupiedEntry{ dict, pos } ),
+            Err( pos ) => Entry::Vacant  ( VacantEntry{ dict, pos, hash, key } ),
+        }
+    }
+    /// Return the key of this entry.
+    pub fn key( &self ) -> &str {
+        match *self {
+            Entry::Occupied( ref e ) => e.key(),
+            Entry::Vacant  ( ref e ) => e.key(),
+        }
+    }
+    /// Insert _default_ if the entry is vacant, and return a mutable reference to the value.
+    pub fn or_insert( self, default : T ) -> &'a mut T {
+        self.or_insert_with( || default )
+    }
+    /// Insert the result of _default_ if the entry is vacant, and return a mutable reference to
+    /// the value.
+    pub fn or_insert_with<F: FnOnce() -> T>( self, default : F ) -> &'a mut T {
+        self.or_insert_with_key( |_| default() )
+    }
+    /// Insert the result of calling _default_ with the key if the entry is vacant, and return a
+    /// mutable reference to the value.
+    pub fn or_insert_with_key<F: FnOnce( &str ) -> T>( self, default : F ) -> &'a mut T {
+        match self {
+            Entry::Occupied( e ) => e.into_mut(),
+            Entry::Vacant  ( e ) => {
+                let val = default( &e.key );
+                e.insert( val )
+            },
+        }
+    }
+}
+
+impl<'a, T> OccupiedEntry<'a, T> {
+    /// Return the key of this entry.
+    pub fn key( &self ) -> &str {
+        &self.dict[self.pos].key
+    }
+    /// Return a reference to the value.
+    pub fn get( &self ) -> &T {
+        &self.dict[self.pos].val
+    }
+    /// Return a mutable reference to the value.
+    pub fn get_mut( &mut self ) -> &mut T {
+        &mut self.dict[self.pos].val
+    }
+    /// Convert the entry into a mutable reference to the value, bound to the Dict lifetime.
+    pub fn into_mut( self ) -> &'a mut T {
+        &mut self.dict[self.pos].val
+    }
+    /// Replace the value with _val_ and return the old one.
+    pub fn insert( &mut self, val : T ) -> T {
+        ::std::mem::replace( self.get_mut(), val )
+    }
+    /// Remove the element from the Dict and return its value.
+    pub fn remove( self ) -> T {
+        let entry = self.dict.remove( self.pos );
+        debug_check_invariant( self.dict );
+        entry.val
+    }
+}
+
+impl<'a, T> VacantEntry<'a, T> {
+    /// Return the key of this entry.
+    pub fn key( &self ) -> &str {
+        &self.key
+    }
+    /// Insert _val_ under the key of this entry, and return a mutable reference to it.
+    pub fn insert( self, val : T ) -> &'a mut T {
+        let VacantEntry{ dict, pos, hash, key } = self;
+        dict.insert( pos, DictEntry{ hash, key, val } );
+        debug_check_invariant( dict );
+        &mut dict[pos].val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use DictIface;
+    use super::*;
+
+    #[test]
+    fn or_insert_with_key_derives_value_from_key() {
+        let mut dict = Dict::<usize>::new();
+        *dict.entry( "abcd".to_string() ).or_insert_with_key( |k| k.len() ) += 1;
+        assert_eq!( dict.get( "abcd" ), Some( &5 ) );
+        // occupied entries keep their value
+        assert_eq!( *dict.entry( "abcd".to_string() ).or_insert_with_key( |_| 0 ), 5 );
+        assert_eq!( dict.len(), 1 );
+    }
+
+    #[test]
+    fn or_insert_keeps_dict_sorted() {
+        let mut dict = Dict::<i32>::new();
+        dict.entry( "z".to_string() ).or_insert( 1 );
+        dict.entry( "a".to_string() ).or_insert_with( || 2 );
+        *dict.entry( "z".to_string() ).or_insert( 0 ) += 10;
+        assert_eq!( dict.len(), 2 );
+        assert_eq!( dict.get( "z" ), Some( &11 ) );
+        assert_eq!( dict.get( "a" ), Some( &2 ) );
+        assert!( dict.windows( 2 ).all( |w| w[0].hash < w[1].hash ) );
+    }
+}
@@ -0,0 +1,95 @@
+//!
+//! Read-only view of a Dict.
+//!
+
+use { Dict, DictEntry, DictIface };
+
+/// Immutable dictionary. Unlike a Dict it gives no access to the underlying Vec, so it can be
+/// handed out without risk of modification. Use thaw() to get the mutable Dict back.
+///
+/// ```compile_fail
+/// use dict::{ Dict, DictIface };
+///
+/// let mut frozen = Dict::<i32>::new().freeze();
+/// frozen.add( "key".to_string(), 1 );
+/// ```
+pub struct FrozenDict<T> { dict : Dict<T> }
+
+impl<T> FrozenDict<T> {
+    pub(crate) fn new( dict : Dict<T> ) -> FrozenDict<T> {
+        FrozenDict{ dict }
+    }
+    /// Return a reference to the value identified by the key _key_, if exists.
+    pub fn get( &self, key : &str ) -> Option<&T> {
+        self.dict.get( key )
+    }
+    /// Return true if an element identified by the key _key_ exists.
+    pub fn contains_key( &self, key : &str ) -> bool {
+        self.dict.contains_key( key )
+    }
+    /// Return the number of elements.
+    pub fn len( &self ) -> usize {
+        self.dict.len()
+    }
+    /// Return true if there are no elements.
+    pub fn is_empty( &self ) -> bool {
+        self.dict.is_empty()
+    }
+    /// Iterate over the elements in hash order.
+    pub fn iter( &self ) -> ::std::slice::Iter<'_, DictEntry<T>> {
+        self.dict.iter()
+    }
+    /// Consume the view and return the mutable Dict.
+    pub fn thaw( self ) -> Dict<T> {
+        self.dict
+    }
+}
+
+impl<'a, T> IntoIterator for &'a FrozenDict<T> {
+    type Item     = &'a DictEntry<T>;
+    type IntoIter = ::std::slice::Iter<'a, DictEntry<T>>;
+    fn into_iter( self ) -> Self::IntoIter {
+        self.dict.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_operations_and_thaw() {
+        let mut dict = Dict::<i32>::new();
+        dict.add( "a".to_string(), 1 );
+        dict.add( "b".to_string(), 2 );
+        let frozen = dict.freeze();
+        assert_eq!( frozen.get( "a" ), Some( &1 ) );
+        assert!( frozen.contains_key( "b" ) );
+        assert!( !frozen.contains_key( "c" ) );
+        assert_eq!( frozen.len(), 2 );
+        assert!( !frozen.is_empty() );
+        assert_eq!( frozen.iter().map( |o| o.val ).sum::<i32>(), 3 );
+        assert_eq!( ( &frozen ).into_iter().count(), 2 );
+        let mut dict = frozen.thaw();
+        assert!( dict.add( "c".to_string(), 3 ) );
+        assert_eq!( dict.len(), 3 );
+    }
+
+    #[test]
+    fn arc_frozen_is_shared_across_threads() {
+        use std::thread;
+
+        let mut dict = Dict::<i32>::with_capacity( 100 );
+        for i in 0..10 {
+            dict.add( format!( "k{}", i ), i );
+        }
+        let frozen  = dict.into_arc_frozen();
+        let handles : Vec<thread::JoinHandle<i32>> = ( 0..4 ).map( |_| {
+            let frozen = frozen.clone();
+            thread::spawn( move || ( 0..10 ).map( |i| *frozen.get( &format!( "k{}", i ) ).unwrap() ).sum() )
+        } ).collect();
+        for handle in handles {
+            assert_eq!( handle.join().unwrap(), 45 );
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//!
+//! Dictionary stamping its elements with an insertion generation.
+//!
+
+use { MetaDict, MetaDictIface };
+
+/// Dictionary stamping each added element with a generation number that grows by one on every
+/// insert, so that consumers can poll for the elements added since they last looked.
+pub struct GenerationalDict<T> { entries : MetaDict<T, u64>, generation : u64 }
+
+impl<T> GenerationalDict<T> {
+    /// Create an empty dictionary at generation 0.
+    pub fn new() -> GenerationalDict<T> {
+        GenerationalDict{ entries: MetaDict::new(), generation: 0 }
+    }
+    /// Add an element _val_ of type T, indexed by the string _key_, stamped with the next
+    /// generation. Returns false, without starting a new generation, if the key exists or there
+    /// is a hash collision
+    pub fn add( &mut self, key : String, val : T ) -> bool {
+        let added = self.entries.add( key, val, self.generation + 1 );
+        if added {
+            self.generation += 1;
+        }
+        added
+    }
+    /// Return the generation of the last added element, or 0 if none was added yet.
+    pub fn current_generation( &self ) -> u64 {
+        self.generation
+    }
+    /// Return the key/value pairs added after generation _gen_, in hash order.
+    pub fn entries_since( &self, gen : u64 ) -> Vec<(&String, &T)> {
+        self.entries.iter().filter( |o| o.meta > gen ).map( |o| ( &o.key, &o.val ) ).collect()
+    }
+    /// Return the generation in which the element identified by the key _key_ was added, if
+    /// exists.
+    pub fn generation_of( &self, key : &str ) -> Option<u64> {
+        self.entries.get_meta( key ).cloned()
+    }
+    /// Return a reference to the value identified by the key _key_, if exists.
+    pub fn get( &self, key : &str ) -> Option<&T> {
+        self.entries.get( key )
+    }
+    /// Return true if an element identified by the key _key_ exists.
+    pub fn contains_key( &self, key : &str ) -> bool {
+        self.entries.contains_key( key )
+    }
+    /// Remove the element identified by the key _key_ and return it, if exists.
+    pub fn remove_key( &mut self, key : &str ) -> Option<T> {
+        self.entries.remove_key( key ).map( |( val, _ )| val )
+    }
+    /// Return the number of elements.
+    pub fn len( &self ) -> usize {
+        self.entries.len()
+    }
+    /// Return true if there are no elements.
+    pub fn is_empty( &self ) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<T> Default for GenerationalDict<T> {
+    fn default() -> GenerationalDict<T> {
+        GenerationalDict::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generation_grows_on_successful_add() {
+        let mut dict = GenerationalDict::<i32>::new();
+        assert_eq!( dict.current_generation(), 0 );
+        assert!( dict.add( "a".to_string(), 1 ) );
+        assert!( dict.add( "b".to_string(), 2 ) );
+        assert_eq!( dict.current_generation(), 2 );
+        assert!( !dict.add( "a".to_string(), 9 ) );
+        assert_eq!( dict.current_generation(), 2 );
+        assert_eq!( dict.generation_of( "b" ), Some( 2 ) );
+        assert_eq!( dict.generation_of( "x" ), None );
+    }
+
+    #[test]
+    fn entries_since_returns_newer_elements() {
+        let mut dict = GenerationalDict::<i32>::new();
+        dict.add( "a".to_string(), 1 );
+        dict.add( "b".to_string(), 2 );
+        let gen = dict.current_generation();
+        dict.add( "c".to_string(), 3 );
+        dict.add( "d".to_string(), 4 );
+        let mut since = dict.entries_since( gen );
+        since.sort();
+        assert_eq!( since, vec![ ( &"c".to_string(), &3 ), ( &"d".to_string(), &4 ) ] );
+        assert_eq!( dict.entries_since( 0 ).len(), 4 );
+        assert!( dict.entries_since( dict.current_generation() ).is_empty() );
+        dict.remove_key( "d" );
+        dict.add( "e".to_string(), 5 );
+        assert_eq!( dict.generation_of( "e" ), Some( 5 ) );
+        assert_eq!( dict.entries_since( gen ).len(), 2 );
+    }
+}
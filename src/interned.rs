@@ -0,0 +1,160 @@
+//!
+//! Dictionaries with interned keys.
+//!
+//! An InternedDict stores its keys as `Rc<str>` taken from an Interner, so several dictionaries
+//! created from the same Interner share a single allocation per distinct key string.
+//!
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use { debug_check_hashes, hash_f };
+
+/// Pool of key strings shared between dictionaries. Cloning an Interner yields a handle to the
+/// same pool. Keys stay in the pool after every dictionary removed them, until purge() is called.
+#[derive(Clone, Default)]
+pub struct Interner { pool : Rc<RefCell<HashSet<Rc<str>>>> }
+
+impl Interner {
+    /// Create an empty key pool.
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+    /// Return the shared allocation for _key_, adding it to the pool if it is not there yet.
+    pub fn intern( &self, key : &str ) -> Rc<str> {
+        let mut pool = self.pool.borrow_mut();
+        if let Some( k ) = pool.get( key ) {
+            return k.clone();
+        }
+        let k : Rc<str> = Rc::from( key );
+        pool.insert( k.clone() );
+        k
+    }
+    /// Drop the keys that are no longer used by any dictionary, and return how many were dropped.
+    pub fn purge( &self ) -> usize {
+        let mut pool = self.pool.borrow_mut();
+        let len      = pool.len();
+        pool.retain( |k| Rc::strong_count( k ) > 1 );
+        len - pool.len()
+    }
+    /// Return the number of distinct keys in the pool.
+    pub fn len( &self ) -> usize {
+        self.pool.borrow().len()
+    }
+    /// Return true if the pool holds no keys.
+    pub fn is_empty( &self ) -> bool {
+        self.pool.borrow().is_empty()
+    }
+}
+
+pub struct InternedEntry<T> { hash : u64, pub key : Rc<str>, pub val : T }
+
+/// Dictionary whose keys are interned in a shared Interner. Entries are kept sorted by key hash,
+/// just like a Dict.
+pub struct InternedDict<T> { interner : Interner, entries : Vec<InternedEntry<T>> }
+
+impl<T> InternedDict<T> {
+    /// Create an empty dictionary that interns its keys in _interner_.
+    pub fn new( interner : &Interner ) -> InternedDict<T> {
+        InternedDict{ interner: interner.clone(), entries: Vec::new() }
+    }
+    /// Add an element _val_ of type T, indexed by the string _key_. Returns false if the key
+    /// exists or there is a hash collision
+    pub fn add( &mut self, key : &str, val : T ) -> bool {
+        let hash = hash_f( key );
+        match self.entries.binary_search_by_key( &hash, |o| o.hash ) {
+            Ok (  _  ) => return false,   // key exists or hash collision
+            Err( pos ) => {
+                let key = self.interner.intern( key );
+                self.entries.insert( pos, InternedEntry{ hash, key, val } )
+            },
+        }
+        debug_check_hashes( self.entries.iter().map( |o| o.hash ) );
+        true
+    }
+    /// Remove the element identified by the key _key_ and return it, if exists.
+    pub fn remove_key( &mut self, key : &str ) -> Option<T> {
+        if let Ok( pos ) = self.entries.binary_search_by_key( &hash_f(key), |o| o.hash ) {
+            let entry = self.entries.remove( pos );
+            debug_check_hashes( self.entries.iter().map( |o| o.hash ) );
+            Some( entry.val )
+        } else { None }
+    }
+    /// Return a reference to the value identified by the key _key_, if exists.
+    pub fn get( &self, key : &str ) -> Option<&T> {
+        self.get_entry( key ).map( |o| &o.val )
+    }
+    /// Return the interned key matching _key_, if exists.
+    pub fn get_key( &self, key : &str ) -> Option<&Rc<str>> {
+        self.get_entry( key ).map( |o| &o.key )
+    }
+    /// Return true if an element identified by the key _key_ exists.
+    pub fn contains_key( &self, key : &str ) -> bool {
+        self.get_entry( key ).is_some()
+    }
+    /// Return the number of elements.
+    pub fn len( &self ) -> usize {
+        self.entries.len()
+    }
+    /// Return true if there are no elements.
+    pub fn is_empty( &self ) -> bool {
+        self.entries.is_empty()
+    }
+    /// Iterate over the entries in hash order.
+    pub fn iter( &self ) -> ::std::slice::Iter<'_, InternedEntry<T>> {
+        self.entries.iter()
+    }
+
+    fn get_entry( &self, key : &str ) -> Option<&InternedEntry<T>> {
+        if let Ok( pos ) = self.entries.binary_search_by_key( &hash_f(key), |o| o.hash ) {
+            Some( &self.entries[pos] )
+        } else { None }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a InternedDict<T> {
+    type Item     = &'a InternedEntry<T>;
+    type IntoIter = ::std::slice::Iter<'a, InternedEntry<T>>;
+    fn into_iter( self ) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dicts_share_key_allocation() {
+        let interner = Interner::new();
+        let mut a    = InternedDict::new( &interner );
+        let mut b    = InternedDict::new( &interner );
+        assert!( a.add( "host", 1 ) );
+        assert!( b.add( "host", 2 ) );
+        assert!( !a.add( "host", 3 ) );
+        assert!( Rc::ptr_eq( a.get_key( "host" ).unwrap(), b.get_key( "host" ).unwrap() ) );
+        assert_eq!( interner.len(), 1 );
+        assert_eq!( a.get( "host" ), Some( &1 ) );
+        assert_eq!( b.get( "host" ), Some( &2 ) );
+    }
+
+    #[test]
+    fn purge_drops_unused_keys() {
+        let interner = Interner::new();
+        let mut a    = InternedDict::new( &interner );
+        let mut b    = InternedDict::new( &interner );
+        a.add( "host", 1 );
+        a.add( "port", 2 );
+        b.add( "host", 3 );
+        assert_eq!( a.remove_key( "port" ), Some( 2 ) );
+        assert_eq!( a.remove_key( "host" ), Some( 1 ) );
+        assert_eq!( interner.len(), 2 );
+        assert_eq!( interner.purge(), 1 );
+        assert_eq!( interner.len(), 1 );
+        assert_eq!( b.get_key( "host" ).map( |k| &**k ), Some( "host" ) );
+        assert_eq!( b.remove_key( "host" ), Some( 3 ) );
+        assert_eq!( interner.purge(), 1 );
+        assert!( interner.is_empty() );
+    }
+}
@@ -51,6 +51,10 @@
 //! assert_eq!( dict.len(), 1 );
 //! ```
 //!
+//! # Features
+//!
+//! * `rand`: enables random_entry() to pick a random element
+//!
 //! # More information
 //!
 //! Copyleft 2018 by Ignacio Nunez Hernanz - nacho _at_ ownyourbits _dot_ com
@@ -60,18 +64,173 @@
 //! More at [OwnYouBits](https://ownyourbits.com)
 //!
 
+#[cfg(feature = "rand")]
+extern crate rand;
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::io::{ self, BufRead, BufReader, Read, Write };
 use std::hash::{Hash, Hasher};
+use std::ops::AddAssign;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::collections::{ BTreeMap, HashMap };
 use std::collections::hash_map::DefaultHasher;
 
+mod auditing;
+mod bytes;
+mod cursor;
+mod entry;
+mod frozen;
+mod generational;
+mod interned;
+mod lru;
+mod merge;
+mod meta;
+mod patch;
+mod query;
+mod salted;
+mod tracked;
+
+pub use auditing::{ AuditingDict, RejectReason };
+pub use bytes::{ AsBytes, FromBytes };
+pub use cursor::DictCursor;
+pub use entry::{ Entry, OccupiedEntry, VacantEntry };
+pub use frozen::FrozenDict;
+pub use generational::GenerationalDict;
+pub use interned::{ Interner, InternedEntry, InternedDict };
+pub use lru::LruDict;
+pub use meta::{ MetaDict, MetaDictEntry, MetaDictIface, TimedDict, TimedDictIface, VersionedDict, VersionedDictIface };
+pub use patch::{ Patch, PatchOp };
+pub use query::QueryResult;
+pub use salted::SaltedDict;
+pub use tracked::TrackedDict;
+
+use merge::{ Merged, MergeWalk };
+
+#[derive(Clone)]
 pub struct DictEntry<T> { hash : u64, pub key : String, pub val : T }
 
 pub type Dict<T> = Vec<DictEntry<T>>;
 
+/// Error returned when a key that must exist is missing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyNotFound { pub key : String }
+
+impl fmt::Display for KeyNotFound {
+    fn fmt( &self, f : &mut fmt::Formatter ) -> fmt::Result {
+        write!( f, "key not found: {}", self.key )
+    }
+}
+
+impl Error for KeyNotFound {}
+
+/// Error returned when a key contains a disallowed character.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidKey { pub key : String, pub ch : char }
+
+impl fmt::Display for InvalidKey {
+    fn fmt( &self, f : &mut fmt::Formatter ) -> fmt::Result {
+        write!( f, "invalid character {:?} in key: {}", self.ch, self.key )
+    }
+}
+
+impl Error for InvalidKey {}
+
+/// Summary of the values of a dictionary, returned by DictIface::value_statistics().
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueStats { pub count : usize, pub sum : f64, pub mean : f64, pub min : f64, pub max : f64 }
+
 pub trait DictIface<T> {
     fn add( &mut self, key : String, val : T ) -> bool;
     fn get( &self, key : &str ) -> Option<&T>;
     fn contains_key( &self, key : &str ) -> bool;
     fn remove_key( &mut self, key : &str ) -> Option<T>;
+    fn total_key_bytes( &self ) -> usize;
+    fn replace_contents( &mut self, new_pairs : Vec<(String, T)> );
+    fn entry( &mut self, key : String ) -> Entry<'_, T>;
+    fn entry_slice( &self ) -> &[DictEntry<T>];
+    fn values_contiguous( &self ) -> Vec<&T>;
+    fn interpolate( &self, template : &str ) -> String where T: AsRef<str>;
+    fn matches_hashmap( &self, map : &HashMap<String, T> ) -> bool where T: PartialEq;
+    fn group_by_value<K: Eq + Hash, F: FnMut( &T ) -> K>( &self, f : F ) -> HashMap<K, Vec<&String>>;
+    fn get_mut( &mut self, key : &str ) -> Option<&mut T>;
+    fn remove_first_by_value( &mut self, val : &T ) -> Option<(String, T)> where T: PartialEq;
+    fn sort_by_hash( &mut self );
+    fn iter_sorted_by_key<'a>( &'a self ) -> impl Iterator<Item = (&'a String, &'a T)> where T: 'a;
+    fn key_first_char_histogram( &self ) -> BTreeMap<char, usize>;
+    fn remove_key_required( &mut self, key : &str ) -> Result<T, KeyNotFound>;
+    fn try_map_values<U, E, F: FnMut( T ) -> Result<U, E>>( self, f : F ) -> Result<Dict<U>, E>;
+    fn near_duplicate_keys( &self ) -> Vec<(String, String)>;
+    fn cursor_mut( &mut self ) -> DictCursor<'_, T>;
+    fn extend_sorted<I: IntoIterator<Item = (u64, String, T)>>( &mut self, iter : I );
+    fn freeze( self ) -> FrozenDict<T>;
+    fn closest_keys( &self, query : &str, max_distance : usize ) -> Vec<&String>;
+    fn chunks( &self, n : usize ) -> Vec<Dict<T>> where T: Clone;
+    fn query<F: FnMut( &str, &T ) -> bool>( &self, pred : F ) -> QueryResult<'_, T>;
+    fn swap_with( &mut self, other : &mut Dict<T> );
+    fn normalize_keys<F: FnMut( &str ) -> String>( &mut self, f : F );
+    fn into_btreemap( self ) -> BTreeMap<String, T>;
+    fn reserve_exact_entries( &mut self, additional : usize );
+    fn changed_since<'a>( &'a self, baseline : &'a Dict<T> ) -> Vec<(&'a String, &'a T, &'a T)> where T: PartialEq;
+    fn write_to<W: Write>( &self, w : &mut W ) -> io::Result<()> where T: AsBytes;
+    fn read_from<R: Read>( r : &mut R ) -> io::Result<Dict<T>> where T: FromBytes;
+    fn bulk_increment( &mut self, updates : &[(&str, T)] ) where T: AddAssign + Clone + Default;
+    fn key_at( &self, index : usize ) -> Option<&String>;
+    fn value_at( &self, index : usize ) -> Option<&T>;
+    #[cfg(feature = "rand")]
+    fn random_entry<R: rand::Rng>( &self, rng : &mut R ) -> Option<(&String, &T)>;
+    fn iter_with_hash<'a>( &'a self ) -> impl Iterator<Item = (u64, &'a String, &'a T)> where T: 'a;
+    fn content_hash( &self ) -> u64 where T: Hash;
+    fn add_validated( &mut self, key : String, val : T, allowed : &dyn Fn( char ) -> bool ) -> Result<bool, InvalidKey>;
+    fn to_alist( &self ) -> Vec<(String, T)> where T: Clone;
+    fn from_alist( alist : Vec<(String, T)> ) -> Dict<T>;
+    fn load_kv_lines<R: Read>( &mut self, r : R ) -> io::Result<usize> where T: FromStr;
+    fn is_subset( &self, other : &Dict<T> ) -> bool where T: PartialEq;
+    fn entries_matching_glob<'a>( &'a self, pattern : &str ) -> Vec<(&'a String, &'a T)>;
+    fn transaction<E, F: FnOnce( &mut Dict<T> ) -> Result<(), E>>( &mut self, f : F ) -> Result<(), E> where T: Clone;
+    fn to_env_string( &self, prefix : &str ) -> String where T: fmt::Display;
+    fn key_union( &self, other : &Dict<T> ) -> Vec<String>;
+    fn entry_size() -> usize;
+    fn try_for_each_value_mut<E, F: FnMut( &mut T ) -> Result<(), E>>( &mut self, f : F ) -> Result<(), E>;
+    fn into_columns( self ) -> (Vec<String>, Vec<T>);
+    fn nearest_by_hash( &self, key : &str ) -> Option<(&String, &T)>;
+    fn key_fingerprints( &self ) -> Vec<u64>;
+    fn extract_where<F: FnMut( &str, &T ) -> bool>( &mut self, pred : F ) -> Dict<T>;
+    fn has_overlap( &self, other : &Dict<T> ) -> bool;
+    fn to_table( &self ) -> String where T: fmt::Display;
+    fn merge_add( &mut self, other : Dict<T> ) where T: AddAssign;
+    fn is_hash_sorted( &self ) -> bool;
+    fn remove_all_by_value( &mut self, val : &T ) -> Vec<(String, T)> where T: PartialEq;
+    fn invert( &self ) -> HashMap<T, Vec<String>> where T: Eq + Hash + Clone;
+    fn join<'a, U>( &'a self, other : &'a Dict<U> ) -> impl Iterator<Item = (&'a String, &'a T, &'a U)> where T: 'a, U: 'a;
+    fn into_arc_frozen( self ) -> Arc<FrozenDict<T>>;
+    fn value_counts( &self ) -> HashMap<T, usize> where T: Eq + Hash + Clone;
+    fn sorted_by<F: FnMut( &(&String, &T), &(&String, &T) ) -> Ordering>( &self, cmp : F ) -> Vec<(&String, &T)>;
+    fn symmetric_difference_keys( &self, other : &Dict<T> ) -> Vec<String>;
+    fn fill_defaults( &mut self, defaults : &Dict<T> ) where T: Clone;
+    fn blank_value_keys( &self ) -> Vec<&String> where T: AsRef<str>;
+    fn hash_adjacent_pairs<'a>( &'a self ) -> impl Iterator<Item = ((&'a String, &'a T), (&'a String, &'a T))> where T: 'a;
+    fn average_hash_gap( &self ) -> Option<f64>;
+    fn compare_and_swap( &mut self, key : &str, expected : &T, new : T ) -> Result<T, Option<T>> where T: PartialEq + Clone;
+    fn keys_where<F: FnMut( &str ) -> bool>( &self, pred : F ) -> Vec<&String>;
+    fn stable_iter<'a>( &'a self ) -> impl Iterator<Item = (&'a String, &'a T)> where T: 'a;
+    fn remove_where<F: FnMut( &str, &T ) -> bool>( &mut self, pred : F ) -> usize;
+    fn get_parsed<F: FromStr>( &self, key : &str ) -> Option<Result<F, F::Err>> where T: AsRef<str>;
+    fn sorted_by_key_fn<K: Ord, F: FnMut( &str ) -> K>( &self, f : F ) -> Vec<(&String, &T)>;
+    fn drain_first_n( &mut self, n : usize ) -> Vec<(String, T)>;
+    fn reindex_by_value<F: FnMut( &T ) -> String>( &self, f : F ) -> Dict<T> where T: Clone;
+    fn to_query_string( &self ) -> String where T: fmt::Display;
+    fn from_query_string( query : &str ) -> Option<Dict<T>> where T: FromStr;
+    fn add_namespaced( &mut self, namespace : &str, other : &Dict<T> ) where T: Clone;
+    fn validate_all<F: FnMut( &str, &T ) -> Result<(), String>>( &self, f : F ) -> Result<(), Vec<(String, String)>>;
+    fn make_patch( &self, target : &Dict<T> ) -> Patch<T> where T: Clone + PartialEq;
+    fn apply_patch( &mut self, patch : Patch<T> );
+    fn write_ndjson<W: Write>( &self, w : &mut W ) -> io::Result<()> where T: fmt::Display;
+    fn value_statistics( &self ) -> Option<ValueStats> where T: Into<f64> + Copy;
+    fn dedup_by_value( &mut self ) where T: PartialEq;
+    fn get_with_fallback( &self, key : &str, separator : char ) -> Option<&T>;
 }
 
 impl<T> DictIface<T> for Dict<T> {
@@ -82,12 +241,14 @@ impl<T> DictIface<T> for Dict<T> {
             Ok (  _  ) => return false,   // key exists or hash collision
             Err( pos ) => self.insert( pos, DictEntry{ hash: hash_f( &key ) , key, val } ),
         }
+        debug_check_invariant( self );
         true
     }
     /// Remove the element identified by the key _key_ and return it, if exists.
     fn remove_key( &mut self, key : &str ) -> Option<T> {
         if let Ok( pos ) = self.binary_search_by_key( &hash_f(key), |o| o.hash ) {
             let entry = self.remove( pos );
+            debug_check_invariant( self );
             Some( entry.val )
         } else { None }
     }
@@ -101,6 +262,729 @@ impl<T> DictIface<T> for Dict<T> {
     fn contains_key( &self, key : &str ) -> bool {
         self.get( key ).is_some()
     }
+    /// Return the sum of the byte lengths of all the keys.
+    fn total_key_bytes( &self ) -> usize {
+        self.iter().map( |o| o.key.len() ).sum()
+    }
+    /// Replace all the elements with the pairs in _new_pairs_, reusing the current allocation.
+    /// Like add(), only the first pair is kept when several share a key or a hash.
+    fn replace_contents( &mut self, new_pairs : Vec<(String, T)> ) {
+        self.clear();
+        self.extend( new_pairs.into_iter().map( |(key, val)| DictEntry{ hash: hash_f( &key ), key, val } ) );
+        self.sort_by_key( |o| o.hash );
+        self.dedup_by_key( |o| o.hash );
+        debug_check_invariant( self );
+    }
+    /// Return the entry identified by the key _key_ for in-place manipulation.
+    fn entry( &mut self, key : String ) -> Entry<'_, T> {
+        let hash = hash_f( &key );
+        Entry::new( self, hash, key )
+    }
+    /// Return the elements as a read-only slice, in hash order.
+    fn entry_slice( &self ) -> &[DictEntry<T>] {
+        self.as_slice()
+    }
+    /// Return references to all the values, in hash order.
+    fn values_contiguous( &self ) -> Vec<&T> {
+        self.iter().map( |o| &o.val ).collect()
+    }
+    /// Return _template_ with every `${key}` placeholder replaced by the value identified by
+    /// _key_. Placeholders for missing keys, an empty `${}` even if the empty key exists, and an
+    /// unterminated `${` are copied verbatim. `$$` produces a literal `$`.
+    fn interpolate( &self, template : &str ) -> String where T: AsRef<str> {
+        let mut out  = String::with_capacity( template.len() );
+        let mut rest = template;
+        while let Some( pos ) = rest.find( '$' ) {
+            out.push_str( &rest[..pos] );
+            rest = &rest[pos..];
+            if rest.starts_with( "$$" ) {
+                out.push( '$' );
+                rest = &rest[2..];
+            } else if rest.starts_with( "${" ) {
+                let end = match rest.find( '}' ) {
+                    Some( end ) => end,
+                    None        => break,   // unterminated, copied verbatim below
+                };
+                match self.get( &rest[2..end] ).filter( |_| end > 2 ) {
+                    Some( val ) => out.push_str( val.as_ref() ),
+                    None        => out.push_str( &rest[..=end] ),
+                }
+                rest = &rest[end + 1..];
+            } else {
+                out.push( '$' );
+                rest = &rest[1..];
+            }
+        }
+        out.push_str( rest );
+        out
+    }
+    /// Return true if _map_ holds exactly the same key/value pairs.
+    fn matches_hashmap( &self, map : &HashMap<String, T> ) -> bool where T: PartialEq {
+        self.len() == map.len() && self.iter().all( |o| map.get( &o.key ) == Some( &o.val ) )
+    }
+    /// Return the keys grouped by the result of applying _f_ to their values. Keys within each
+    /// group are in hash order.
+    fn group_by_value<K: Eq + Hash, F: FnMut( &T ) -> K>( &self, mut f : F ) -> HashMap<K, Vec<&String>> {
+        let mut groups = HashMap::new();
+        for o in self.iter() {
+            groups.entry( f( &o.val ) ).or_insert_with( Vec::new ).push( &o.key );
+        }
+        groups
+    }
+    /// Return a mutable reference to the value identified by the key _key_, if exists.
+    ///
+    /// Dict is an alias of Vec, so `Index<&str>` and `IndexMut<&str>` cannot be implemented for
+    /// it. Use `*dict.get_mut( "key" ).unwrap() += 1` instead of `dict["key"] += 1`.
+    fn get_mut( &mut self, key : &str ) -> Option<&mut T> {
+        if let Ok( pos ) = self.binary_search_by_key( &hash_f(key), |o| o.hash ) {
+            Some( &mut self[pos].val )
+        } else { None }
+    }
+    /// Remove the first element, in hash order, whose value equals _val_ and return it as a
+    /// key/value pair, if exists.
+    fn remove_first_by_value( &mut self, val : &T ) -> Option<(String, T)> where T: PartialEq {
+        let pos   = self.iter().position( |o| o.val == *val )?;
+        let entry = self.remove( pos );
+        debug_check_invariant( self );
+        Some( ( entry.key, entry.val ) )
+    }
+    /// Restore the hash order after the elements have been reordered or pushed through Vec
+    /// methods. Duplicates are not removed: if there may be any, follow with
+    /// `dedup_by( |a, b| a.key == b.key )`.
+    fn sort_by_hash( &mut self ) {
+        self.sort_unstable_by_key( |o| o.hash );
+    }
+    /// Iterate over the key/value pairs in ascending lexical key order. Building the sorted index
+    /// costs O(n log n) up front, each step is then O(1).
+    fn iter_sorted_by_key<'a>( &'a self ) -> impl Iterator<Item = (&'a String, &'a T)> where T: 'a {
+        let mut index : Vec<usize> = ( 0..self.len() ).collect();
+        index.sort_unstable_by( |&a, &b| self[a].key.cmp( &self[b].key ) );
+        index.into_iter().map( move |i| ( &self[i].key, &self[i].val ) )
+    }
+    /// Return the number of keys starting with each character. Empty keys are not counted.
+    fn key_first_char_histogram( &self ) -> BTreeMap<char, usize> {
+        let mut histogram = BTreeMap::new();
+        for c in self.iter().filter_map( |o| o.key.chars().next() ) {
+            *histogram.entry( c ).or_insert( 0 ) += 1;
+        }
+        histogram
+    }
+    /// Remove the element identified by the key _key_ and return it, or an error naming the key
+    /// if it does not exist.
+    fn remove_key_required( &mut self, key : &str ) -> Result<T, KeyNotFound> {
+        self.remove_key( key ).ok_or_else( || KeyNotFound{ key: key.to_string() } )
+    }
+    /// Consume the dictionary and convert every value with _f_, in hash order. Returns the first
+    /// error produced by _f_, if any.
+    fn try_map_values<U, E, F: FnMut( T ) -> Result<U, E>>( self, mut f : F ) -> Result<Dict<U>, E> {
+        self.into_iter()
+            .map( |o| Ok( DictEntry{ hash: o.hash, key: o.key, val: f( o.val )? } ) )
+            .collect()
+    }
+    /// Return the pairs of keys that are different but become equal once trimmed and lowercased,
+    /// like "Host" and "host ". Each key is paired with the first one, in hash order, that has
+    /// the same normalized form.
+    fn near_duplicate_keys( &self ) -> Vec<(String, String)> {
+        let mut seen  = HashMap::new();
+        let mut pairs = Vec::new();
+        for o in self.iter() {
+            let first = seen.entry( o.key.trim().to_lowercase() ).or_insert( &o.key );
+            if *first != &o.key {
+                pairs.push( ( first.to_string(), o.key.clone() ) );
+            }
+        }
+        pairs
+    }
+    /// Return a cursor pointing to the first element, able to walk the elements in hash order
+    /// while modifying or removing them.
+    fn cursor_mut( &mut self ) -> DictCursor<'_, T> {
+        DictCursor::new( self )
+    }
+    /// Merge the _(hash, key, val)_ tuples of _iter_, which must be sorted by hash, in a single
+    /// linear pass. Like add(), tuples whose hash is already present are dropped.
+    fn extend_sorted<I: IntoIterator<Item = (u64, String, T)>>( &mut self, iter : I ) {
+        let iter    = iter.into_iter();
+        let mut old = ::std::mem::take( self ).into_iter().peekable();
+        self.reserve( old.len() + iter.size_hint().0 );
+        let mut prev = None;
+        for ( hash, key, val ) in iter {
+            debug_assert!( prev.is_none_or( |p| p <= hash ), "extend_sorted: input is not sorted by hash" );
+            debug_assert!( hash == hash_f( &key ), "extend_sorted: hash does not match key" );
+            prev = Some( hash );
+            while let Some( o ) = old.next_if( |o| o.hash < hash ) {
+                self.push( o );
+            }
+            let exists = old.peek().is_some_and( |o| o.hash == hash )
+                      || self.last().is_some_and( |o| o.hash == hash );
+            if !exists {
+                self.push( DictEntry{ hash, key, val } );
+            }
+        }
+        self.extend( old );
+        debug_check_invariant( self );
+    }
+    /// Consume the dictionary and return a read-only view of it.
+    fn freeze( self ) -> FrozenDict<T> {
+        FrozenDict::new( self )
+    }
+    /// Return the keys within _max_distance_ edits (Levenshtein distance, counted in chars) of
+    /// _query_, closest first. Useful for "did you mean" suggestions.
+    fn closest_keys( &self, query : &str, max_distance : usize ) -> Vec<&String> {
+        let mut found : Vec<(usize, &String)> = self.iter()
+            .map( |o| ( levenshtein( query, &o.key ), &o.key ) )
+            .filter( |&( distance, _ )| distance <= max_distance )
+            .collect();
+        found.sort_by_key( |&( distance, _ )| distance );
+        found.into_iter().map( |( _, key )| key ).collect()
+    }
+    /// Split the elements, in hash order, into exactly _n_ dictionaries whose sizes differ by at
+    /// most one, so some are empty if _n_ is greater than the length. Returns no dictionaries if
+    /// _n_ is 0.
+    ///
+    /// Note that _n_ is the number of chunks: this shadows the chunk size based `slice::chunks`.
+    fn chunks( &self, n : usize ) -> Vec<Dict<T>> where T: Clone {
+        if n == 0 {
+            return Vec::new();
+        }
+        let ( size, extra ) = ( self.len() / n, self.len() % n );
+        let mut rest = self.as_slice();
+        ( 0..n ).map( |i| {
+            let ( chunk, tail ) = rest.split_at( size + if i < extra { 1 } else { 0 } );
+            rest = tail;
+            chunk.to_vec()
+        } ).collect()
+    }
+    /// Return the elements for which _pred_ returns true, called with the key and the value.
+    fn query<F: FnMut( &str, &T ) -> bool>( &self, mut pred : F ) -> QueryResult<'_, T> {
+        QueryResult::new( self.iter().filter( |o| pred( &o.key, &o.val ) ).collect() )
+    }
+    /// Exchange the elements with those of _other_. This is an O(1) swap of the underlying
+    /// buffers, handy for double buffering.
+    fn swap_with( &mut self, other : &mut Dict<T> ) {
+        ::std::mem::swap( self, other );
+    }
+    /// Replace every key with the result of applying _f_ to it. If several keys become equal,
+    /// the one that came last in hash order before normalizing wins.
+    fn normalize_keys<F: FnMut( &str ) -> String>( &mut self, mut f : F ) {
+        for o in self.iter_mut() {
+            o.key  = f( &o.key );
+            o.hash = hash_f( &o.key );
+        }
+        sort_last_wins( self );
+    }
+    /// Consume the dictionary into a BTreeMap, which iterates in lexical key order. Use
+    /// replace_contents() to go the other way.
+    fn into_btreemap( self ) -> BTreeMap<String, T> {
+        self.into_iter().map( |o| ( o.key, o.val ) ).collect()
+    }
+    /// Reserve capacity for exactly _additional_ more elements, see Vec::reserve_exact().
+    fn reserve_exact_entries( &mut self, additional : usize ) {
+        self.reserve_exact( additional );
+    }
+    /// Return the _(key, old value, new value)_ tuples for the keys present both here and in
+    /// _baseline_ whose values differ, the old value being the one in _baseline_.
+    fn changed_since<'a>( &'a self, baseline : &'a Dict<T> ) -> Vec<(&'a String, &'a T, &'a T)> where T: PartialEq {
+        MergeWalk::new( self, baseline ).filter_map( |m| match m {
+            Merged::Both( new, old ) if new.val != old.val => Some( ( &new.key, &old.val, &new.val ) ),
+            _                                              => None,
+        } ).collect()
+    }
+    /// Write the dictionary to _w_ in a compact length-prefixed binary format.
+    fn write_to<W: Write>( &self, w : &mut W ) -> io::Result<()> where T: AsBytes {
+        bytes::write_u64( w, self.len() as u64 )?;
+        for o in self.iter() {
+            bytes::write_chunk( w, o.key.as_bytes() )?;
+            bytes::write_chunk( w, &o.val.to_bytes() )?;
+        }
+        Ok( () )
+    }
+    /// Read a dictionary written by write_to() from _r_.
+    fn read_from<R: Read>( r : &mut R ) -> io::Result<Dict<T>> where T: FromBytes {
+        let count    = bytes::read_u64( r )?;
+        let mut dict = Dict::new();
+        for _ in 0..count {
+            let key = String::from_bytes( bytes::read_chunk( r )? )?;
+            let val = T::from_bytes( bytes::read_chunk( r )? )?;
+            dict.add( key, val );
+        }
+        Ok( dict )
+    }
+    /// Add each delta in _updates_ to the value identified by its key, starting from the default
+    /// value for missing keys. New keys are sorted in once at the end instead of one by one.
+    fn bulk_increment( &mut self, updates : &[(&str, T)] ) where T: AddAssign + Clone + Default {
+        let mut new_entries : Vec<DictEntry<T>> = Vec::new();
+        let mut new_index   = HashMap::new();
+        for &( key, ref delta ) in updates {
+            if let Some( val ) = self.get_mut( key ) {
+                *val += delta.clone();
+                continue;
+            }
+            let pos = *new_index.entry( key ).or_insert_with( || {
+                new_entries.push( DictEntry{ hash: hash_f( key ), key: key.to_string(), val: T::default() } );
+                new_entries.len() - 1
+            } );
+            new_entries[pos].val += delta.clone();
+        }
+        new_entries.sort_by_key( |o| o.hash );
+        self.extend_sorted( new_entries.into_iter().map( |o| ( o.hash, o.key, o.val ) ) );
+    }
+    /// Return the key at position _index_ in hash order, if exists.
+    fn key_at( &self, index : usize ) -> Option<&String> {
+        self.as_slice().get( index ).map( |o| &o.key )
+    }
+    /// Return a reference to the value at position _index_ in hash order, if exists.
+    fn value_at( &self, index : usize ) -> Option<&T> {
+        self.as_slice().get( index ).map( |o| &o.val )
+    }
+    /// Return a uniformly chosen key/value pair, or None if the dictionary is empty.
+    #[cfg(feature = "rand")]
+    fn random_entry<R: rand::Rng>( &self, rng : &mut R ) -> Option<(&String, &T)> {
+        if self.is_empty() {
+            return None;
+        }
+        let o = &self[rng.gen_range( 0..self.len() )];
+        Some( ( &o.key, &o.val ) )
+    }
+    /// Iterate over the elements in hash order, yielding the stored hash of each key along with
+    /// the key and the value.
+    fn iter_with_hash<'a>( &'a self ) -> impl Iterator<Item = (u64, &'a String, &'a T)> where T: 'a {
+        self.iter().map( |o| ( o.hash, &o.key, &o.val ) )
+    }
+    /// Return a digest of all the keys and values, independent of the order of the elements, to
+    /// cheaply detect changes.
+    fn content_hash( &self ) -> u64 where T: Hash {
+        self.iter().fold( 0, |digest, o| digest ^ hash_f( ( &o.key, &o.val ) ) )
+    }
+    /// Add an element like add(), after checking that every character of _key_ passes _allowed_.
+    /// Returns an error naming the first disallowed character, or else the result of add(). The
+    /// bool is kept so that a valid key rejected as a duplicate or hash collision is not mistaken
+    /// for a successful insert.
+    fn add_validated( &mut self, key : String, val : T, allowed : &dyn Fn( char ) -> bool ) -> Result<bool, InvalidKey> {
+        if let Some( ch ) = key.chars().find( |&c| !allowed( c ) ) {
+            return Err( InvalidKey{ key, ch } );
+        }
+        Ok( self.add( key, val ) )
+    }
+    /// Return the elements as an association list of key/value pairs, in hash order.
+    fn to_alist( &self ) -> Vec<(String, T)> where T: Clone {
+        self.iter().map( |o| ( o.key.clone(), o.val.clone() ) ).collect()
+    }
+    /// Create a dictionary from an association list of key/value pairs. Like add(), only the
+    /// first pair is kept when several share a key.
+    fn from_alist( alist : Vec<(String, T)> ) -> Dict<T> {
+        let mut dict = Dict::with_capacity( alist.len() );
+        dict.replace_contents( alist );
+        dict
+    }
+    /// Add the elements read from _r_ as `key=value` lines, and return how many were added.
+    /// Lines are split on the first `=` and both sides are trimmed. Blank lines and lines
+    /// starting with `#` are skipped, while lines without `=` or with a value that can not be
+    /// parsed are reported as InvalidData. Lines are added as they are read, so on error the
+    /// lines before the failing one stay added.
+    fn load_kv_lines<R: Read>( &mut self, r : R ) -> io::Result<usize> where T: FromStr {
+        let mut added = 0;
+        for ( n, line ) in BufReader::new( r ).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with( '#' ) {
+                continue;
+            }
+            let invalid = |msg : &str| io::Error::new( io::ErrorKind::InvalidData, format!( "line {}: {}", n + 1, msg ) );
+            let ( key, val ) = line.split_once( '=' ).ok_or_else( || invalid( "expected key=value" ) )?;
+            let val : T = val.trim().parse().map_err( |_| invalid( "invalid value" ) )?;
+            if self.add( key.trim().to_string(), val ) {
+                added += 1;
+            }
+        }
+        Ok( added )
+    }
+    /// Return true if every key/value pair is also present in _other_.
+    fn is_subset( &self, other : &Dict<T> ) -> bool where T: PartialEq {
+        self.len() <= other.len() && MergeWalk::new( self, other ).all( |m| match m {
+            Merged::Left ( _ )    => false,
+            Merged::Right( _ )    => true,
+            Merged::Both ( a, b ) => a.val == b.val,
+        } )
+    }
+    /// Return the key/value pairs whose key matches the glob _pattern_, where `*` matches any
+    /// sequence of chars and `?` any single char.
+    fn entries_matching_glob<'a>( &'a self, pattern : &str ) -> Vec<(&'a String, &'a T)> {
+        let pattern : Vec<char> = pattern.chars().collect();
+        self.iter()
+            .filter( |o| glob_match( &pattern, &o.key ) )
+            .map( |o| ( &o.key, &o.val ) )
+            .collect()
+    }
+    /// Apply the changes made by _f_ only if it succeeds. If _f_ returns an error, the
+    /// dictionary is restored to a copy taken beforehand and the error is returned.
+    fn transaction<E, F: FnOnce( &mut Dict<T> ) -> Result<(), E>>( &mut self, f : F ) -> Result<(), E> where T: Clone {
+        let snapshot = self.clone();
+        f( self ).inspect_err( |_| *self = snapshot )
+    }
+    /// Return the elements as shell environment assignments, one `PREFIX_KEY=value` line each,
+    /// sorted by variable name. Names are uppercased and every char other than an ASCII letter or
+    /// digit becomes `_`, so "db.host" is exported as PREFIX_DB_HOST. No `_` is added after an
+    /// empty _prefix_, and a `_` is added in front of names that would be empty or start with a
+    /// digit, which are not valid variable names, so "1a" is exported as _1A. If several keys
+    /// map to the same name, the lexically greatest key wins. Values are single quoted unless
+    /// they only contain shell-safe chars.
+    fn to_env_string( &self, prefix : &str ) -> String where T: fmt::Display {
+        let mangle = |s : &str| -> String {
+            s.chars().map( |c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' } ).collect()
+        };
+        let mut vars = BTreeMap::new();
+        for ( key, val ) in self.iter_sorted_by_key() {
+            let mut name = if prefix.is_empty() { mangle( key ) } else { mangle( &format!( "{}_{}", prefix, key ) ) };
+            if name.chars().next().is_none_or( |c| c.is_ascii_digit() ) {
+                name.insert( 0, '_' );
+            }
+            vars.insert( name, shell_quote( &val.to_string() ) );
+        }
+        vars.iter().map( |( name, val )| format!( "{}={}\n", name, val ) ).collect()
+    }
+    /// Return the keys present here, in _other_ or in both, once each and in hash order.
+    fn key_union( &self, other : &Dict<T> ) -> Vec<String> {
+        MergeWalk::new( self, other ).map( |m| match m {
+            Merged::Left ( a ) | Merged::Both( a, _ ) => a.key.clone(),
+            Merged::Right( b )                        => b.key.clone(),
+        } ).collect()
+    }
+    /// Return the size in bytes taken by each element, not counting the heap allocated key
+    /// bytes: the value, the String header of the key and the 8 byte hash.
+    fn entry_size() -> usize {
+        ::std::mem::size_of::<DictEntry<T>>()
+    }
+    /// Apply _f_ to every value in hash order, stopping at the first error, which is returned.
+    /// Values already visited keep their changes.
+    fn try_for_each_value_mut<E, F: FnMut( &mut T ) -> Result<(), E>>( &mut self, mut f : F ) -> Result<(), E> {
+        self.iter_mut().try_for_each( |o| f( &mut o.val ) )
+    }
+    /// Consume the dictionary into a vector of keys and a vector of values, both in hash order so
+    /// that equal positions belong to the same element.
+    fn into_columns( self ) -> (Vec<String>, Vec<T>) {
+        self.into_iter().map( |o| ( o.key, o.val ) ).unzip()
+    }
+    /// Return the key/value pair whose hash is closest to the hash of _key_, measuring distances
+    /// around a ring that wraps at the u64 boundary, as in consistent hashing. On a tie, the
+    /// element preceding the hash of _key_ on the ring wins, which is the last element when no
+    /// stored hash is lower. Returns None if the dictionary is empty.
+    fn nearest_by_hash( &self, key : &str ) -> Option<(&String, &T)> {
+        if self.is_empty() {
+            return None;
+        }
+        let hash = hash_f( key );
+        let pos  = match self.binary_search_by_key( &hash, |o| o.hash ) {
+            Ok ( pos ) => pos,
+            Err( pos ) => {
+                let next     = pos % self.len();
+                let prev     = ( pos + self.len() - 1 ) % self.len();
+                let distance = |i : usize| self[i].hash.wrapping_sub( hash ).min( hash.wrapping_sub( self[i].hash ) );
+                if distance( prev ) <= distance( next ) { prev } else { next }
+            },
+        };
+        Some( ( &self[pos].key, &self[pos].val ) )
+    }
+    /// Return the stored hash of every key, in ascending order. These can be compared against
+    /// hash_of() to test for membership before a full lookup, e.g. through a bloom filter.
+    fn key_fingerprints( &self ) -> Vec<u64> {
+        self.iter().map( |o| o.hash ).collect()
+    }
+    /// Remove the elements for which _pred_ returns true, called with the key and the value, and
+    /// return them as a new dictionary, in a single pass that keeps the current allocation. This
+    /// is Vec::extract_if() for dictionaries, under another name since the Vec method would
+    /// shadow it.
+    fn extract_where<F: FnMut( &str, &T ) -> bool>( &mut self, mut pred : F ) -> Dict<T> {
+        let extracted = self.extract_if( .., |o| pred( &o.key, &o.val ) ).collect();
+        debug_check_invariant( self );
+        extracted
+    }
+    /// Return true if at least one key is present both here and in _other_.
+    fn has_overlap( &self, other : &Dict<T> ) -> bool {
+        MergeWalk::new( self, other ).any( |m| matches!( m, Merged::Both( _, _ ) ) )
+    }
+    /// Return the elements as a two column "Key" / "Value" table sorted by key, with the values
+    /// aligned after the longest key. Widths are counted in chars, so wide or combining chars
+    /// may misalign.
+    fn to_table( &self ) -> String where T: fmt::Display {
+        let width     = self.iter().map( |o| o.key.chars().count() ).fold( "Key".len(), usize::max );
+        let mut table = format!( "{:<width$}  Value\n", "Key", width = width );
+        for ( key, val ) in self.iter_sorted_by_key() {
+            table.push_str( &format!( "{:<width$}  {}\n", key, val, width = width ) );
+        }
+        table
+    }
+    /// Merge _other_ in a single linear pass, adding its values to the existing ones for shared
+    /// keys. Like add(), elements of _other_ whose hash collides with a different key are dropped.
+    fn merge_add( &mut self, other : Dict<T> ) where T: AddAssign {
+        let mut old = ::std::mem::take( self ).into_iter().peekable();
+        self.reserve( old.len() + other.len() );
+        for o in other {
+            while let Some( e ) = old.next_if( |e| e.hash < o.hash ) {
+                self.push( e );
+            }
+            match old.next_if( |e| e.hash == o.hash ) {
+                Some( mut e ) => {
+                    if e.key == o.key {
+                        e.val += o.val;
+                    }
+                    self.push( e );
+                },
+                None => self.push( o ),
+            }
+        }
+        self.extend( old );
+        debug_check_invariant( self );
+    }
+    /// Return true if the elements are in strictly ascending hash order, which lookups rely on.
+    /// This can only be false after reordering or pushing elements through Vec methods.
+    fn is_hash_sorted( &self ) -> bool {
+        self.windows( 2 ).all( |w| w[0].hash < w[1].hash )
+    }
+    /// Remove every element whose value equals _val_, in a single pass that keeps the current
+    /// allocation, and return them as key/value pairs in hash order.
+    fn remove_all_by_value( &mut self, val : &T ) -> Vec<(String, T)> where T: PartialEq {
+        let removed = self.extract_if( .., |o| o.val == *val ).map( |o| ( o.key, o.val ) ).collect();
+        debug_check_invariant( self );
+        removed
+    }
+    /// Return a map from every distinct value to the keys holding it, in hash order.
+    fn invert( &self ) -> HashMap<T, Vec<String>> where T: Eq + Hash + Clone {
+        let mut inverted = HashMap::new();
+        for o in self.iter() {
+            inverted.entry( o.val.clone() ).or_insert_with( Vec::new ).push( o.key.clone() );
+        }
+        inverted
+    }
+    /// Iterate over the keys present both here and in _other_, in hash order, along with their
+    /// value in each dictionary. This is a lazy linear walk over both dictionaries.
+    fn join<'a, U>( &'a self, other : &'a Dict<U> ) -> impl Iterator<Item = (&'a String, &'a T, &'a U)> where T: 'a, U: 'a {
+        MergeWalk::new( self, other ).filter_map( |m| match m {
+            Merged::Both( a, b ) => Some( ( &a.key, &a.val, &b.val ) ),
+            _                    => None,
+        } )
+    }
+    /// Consume the dictionary, shrink it to fit and return it as a read-only view behind an Arc,
+    /// to be shared across threads for lookups.
+    fn into_arc_frozen( mut self ) -> Arc<FrozenDict<T>> {
+        self.shrink_to_fit();
+        Arc::new( self.freeze() )
+    }
+    /// Return how many keys hold each distinct value.
+    fn value_counts( &self ) -> HashMap<T, usize> where T: Eq + Hash + Clone {
+        let mut counts = HashMap::new();
+        for o in self.iter() {
+            *counts.entry( o.val.clone() ).or_insert( 0 ) += 1;
+        }
+        counts
+    }
+    /// Return the key/value pairs sorted by _cmp_. The sort is stable, so pairs comparing equal
+    /// stay in hash order.
+    fn sorted_by<F: FnMut( &(&String, &T), &(&String, &T) ) -> Ordering>( &self, mut cmp : F ) -> Vec<(&String, &T)> {
+        let mut pairs : Vec<(&String, &T)> = self.iter().map( |o| ( &o.key, &o.val ) ).collect();
+        pairs.sort_by( |a, b| cmp( a, b ) );
+        pairs
+    }
+    /// Return the keys present either here or in _other_ but not in both, in hash order.
+    fn symmetric_difference_keys( &self, other : &Dict<T> ) -> Vec<String> {
+        MergeWalk::new( self, other ).filter_map( |m| match m {
+            Merged::Left ( a )    => Some( a.key.clone() ),
+            Merged::Right( b )    => Some( b.key.clone() ),
+            Merged::Both ( _, _ ) => None,
+        } ).collect()
+    }
+    /// Add a copy of every element of _defaults_ whose key is missing, never overwriting
+    /// existing values.
+    fn fill_defaults( &mut self, defaults : &Dict<T> ) where T: Clone {
+        let missing : Vec<(u64, String, T)> = MergeWalk::new( self, defaults ).filter_map( |m| match m {
+            Merged::Right( b ) => Some( ( b.hash, b.key.clone(), b.val.clone() ) ),
+            _                  => None,
+        } ).collect();
+        self.extend_sorted( missing );
+    }
+    /// Return the keys whose value is empty or only made of whitespace.
+    fn blank_value_keys( &self ) -> Vec<&String> where T: AsRef<str> {
+        self.iter().filter( |o| o.val.as_ref().trim().is_empty() ).map( |o| &o.key ).collect()
+    }
+    /// Iterate over every pair of consecutive elements in hash order, as key/value pairs.
+    fn hash_adjacent_pairs<'a>( &'a self ) -> impl Iterator<Item = ((&'a String, &'a T), (&'a String, &'a T))> where T: 'a {
+        self.windows( 2 ).map( |w| ( ( &w[0].key, &w[0].val ), ( &w[1].key, &w[1].val ) ) )
+    }
+    /// Return the mean difference between consecutive stored hashes, or None if there are fewer
+    /// than two elements. Well spread keys give a value close to 2^64 / len.
+    fn average_hash_gap( &self ) -> Option<f64> {
+        if self.len() < 2 {
+            return None;
+        }
+        // the gaps add up to the distance between the first and the last hash
+        let span = self[self.len() - 1].hash - self[0].hash;
+        Some( span as f64 / ( self.len() - 1 ) as f64 )
+    }
+    /// Replace the value identified by the key _key_ with _new_ only if it equals _expected_,
+    /// and return the old value. Otherwise return a copy of the current value, or None if the
+    /// key does not exist.
+    fn compare_and_swap( &mut self, key : &str, expected : &T, new : T ) -> Result<T, Option<T>> where T: PartialEq + Clone {
+        match self.get_mut( key ) {
+            Some( val ) if *val == *expected => Ok( ::std::mem::replace( val, new ) ),
+            Some( val )                      => Err( Some( val.clone() ) ),
+            None                             => Err( None ),
+        }
+    }
+    /// Return the keys for which _pred_ returns true, in hash order.
+    fn keys_where<F: FnMut( &str ) -> bool>( &self, mut pred : F ) -> Vec<&String> {
+        self.iter().map( |o| &o.key ).filter( |key| pred( key ) ).collect()
+    }
+    /// Iterate over the key/value pairs in a reproducible order that doesn't depend on the
+    /// hasher, which may change between Rust versions. Same as iter_sorted_by_key().
+    fn stable_iter<'a>( &'a self ) -> impl Iterator<Item = (&'a String, &'a T)> where T: 'a {
+        self.iter_sorted_by_key()
+    }
+    /// Remove the elements for which _pred_ returns true, called with the key and the value, and
+    /// return how many were removed. Unlike extract_where() nothing is allocated.
+    fn remove_where<F: FnMut( &str, &T ) -> bool>( &mut self, mut pred : F ) -> usize {
+        let len = self.len();
+        self.retain( |o| !pred( &o.key, &o.val ) );
+        debug_check_invariant( self );
+        len - self.len()
+    }
+    /// Return the value identified by the key _key_ parsed as F, or None if the key does not
+    /// exist.
+    fn get_parsed<F: FromStr>( &self, key : &str ) -> Option<Result<F, F::Err>> where T: AsRef<str> {
+        self.get( key ).map( |val| val.as_ref().parse() )
+    }
+    /// Return the key/value pairs sorted by the result of applying _f_ to each key, e.g. for a
+    /// natural sort. _f_ is called once per key, and pairs comparing equal stay in hash order.
+    fn sorted_by_key_fn<K: Ord, F: FnMut( &str ) -> K>( &self, mut f : F ) -> Vec<(&String, &T)> {
+        let mut pairs : Vec<(&String, &T)> = self.iter().map( |o| ( &o.key, &o.val ) ).collect();
+        pairs.sort_by_cached_key( |&( key, _ )| f( key ) );
+        pairs
+    }
+    /// Remove the first _n_ elements in hash order, or all of them if there are fewer, and
+    /// return them as key/value pairs.
+    fn drain_first_n( &mut self, n : usize ) -> Vec<(String, T)> {
+        let n       = n.min( self.len() );
+        let drained = self.drain( ..n ).map( |o| ( o.key, o.val ) ).collect();
+        debug_check_invariant( self );
+        drained
+    }
+    /// Return a copy of the dictionary whose keys are the result of applying _f_ to each value,
+    /// e.g. to index records by an inner id. If several values give the same key, the one that
+    /// comes last in hash order wins.
+    fn reindex_by_value<F: FnMut( &T ) -> String>( &self, mut f : F ) -> Dict<T> where T: Clone {
+        let mut dict : Dict<T> = self.iter().map( |o| {
+            let key = f( &o.val );
+            DictEntry{ hash: hash_f( &key ), key, val: o.val.clone() }
+        } ).collect();
+        sort_last_wins( &mut dict );
+        dict
+    }
+    /// Return the elements as a `key1=val1&key2=val2` query string sorted by key. Every byte of
+    /// keys and values other than ASCII letters, digits and `-._~` is percent-encoded.
+    fn to_query_string( &self ) -> String where T: fmt::Display {
+        self.iter_sorted_by_key()
+            .map( |( key, val )| format!( "{}={}", percent_encode( key ), percent_encode( &val.to_string() ) ) )
+            .collect::<Vec<String>>()
+            .join( "&" )
+    }
+    /// Create a dictionary from a `key1=val1&key2=val2` query string, percent-decoding keys and
+    /// values and accepting `+` for spaces. A pair without `=` has an empty value. Returns None
+    /// if an escape is malformed or a value can not be parsed as T. Like add(), only the first
+    /// of several pairs with the same key is kept.
+    fn from_query_string( query : &str ) -> Option<Dict<T>> where T: FromStr {
+        let mut dict = Dict::new();
+        for pair in query.split( '&' ).filter( |pair| !pair.is_empty() ) {
+            let ( key, val ) = pair.split_once( '=' ).unwrap_or( ( pair, "" ) );
+            dict.add( percent_decode( key )?, percent_decode( val )?.parse().ok()? );
+        }
+        Some( dict )
+    }
+    /// Add a copy of every element of _other_ under the key `namespace.key`. Unlike add(), an
+    /// existing element with the same key, or a colliding hash, is replaced.
+    fn add_namespaced( &mut self, namespace : &str, other : &Dict<T> ) where T: Clone {
+        self.extend( other.iter().map( |o| {
+            let key = format!( "{}.{}", namespace, o.key );
+            DictEntry{ hash: hash_f( &key ), key, val: o.val.clone() }
+        } ) );
+        sort_last_wins( self );
+    }
+    /// Call _f_ with the key and the value of every element. Returns Ok if all of them pass,
+    /// otherwise the key and the error message of every failing element, in hash order.
+    fn validate_all<F: FnMut( &str, &T ) -> Result<(), String>>( &self, mut f : F ) -> Result<(), Vec<(String, String)>> {
+        let errors : Vec<(String, String)> = self.iter()
+            .filter_map( |o| f( &o.key, &o.val ).err().map( |e| ( o.key.clone(), e ) ) )
+            .collect();
+        if errors.is_empty() { Ok( () ) } else { Err( errors ) }
+    }
+    /// Return the fewest changes that turn the dictionary into _target_ when passed to
+    /// apply_patch(), computed in a single linear pass.
+    fn make_patch( &self, target : &Dict<T> ) -> Patch<T> where T: Clone + PartialEq {
+        MergeWalk::new( self, target ).filter_map( |m| match m {
+            Merged::Left ( a )                      => Some( PatchOp::Delete( a.key.clone() ) ),
+            Merged::Right( b )                      => Some( PatchOp::Insert( b.key.clone(), b.val.clone() ) ),
+            Merged::Both ( a, b ) if a.val != b.val => Some( PatchOp::Update( b.key.clone(), b.val.clone() ) ),
+            Merged::Both ( _, _ )                   => None,
+        } ).collect()
+    }
+    /// Apply the changes of _patch_ in order. Like add(), an insert is ignored if the key exists
+    /// or there is a hash collision, and updates or deletes of missing keys are ignored.
+    fn apply_patch( &mut self, patch : Patch<T> ) {
+        for op in patch {
+            match op {
+                PatchOp::Insert( key, val ) => { self.add( key, val ); },
+                PatchOp::Update( key, val ) => if let Some( old ) = self.get_mut( &key ) { *old = val; },
+                PatchOp::Delete( key )      => { self.remove_key( &key ); },
+            }
+        }
+    }
+    /// Write the elements to _w_ sorted by key, one `{"key":"k","val":"v"}` JSON object per line.
+    /// Both the key and the Display output of the value are written as escaped JSON strings.
+    fn write_ndjson<W: Write>( &self, w : &mut W ) -> io::Result<()> where T: fmt::Display {
+        for ( key, val ) in self.iter_sorted_by_key() {
+            writeln!( w, "{{\"key\":{},\"val\":{}}}", json_quote( key ), json_quote( &val.to_string() ) )?;
+        }
+        Ok( () )
+    }
+    /// Return the count, sum, mean, minimum and maximum of the values in a single pass, or None
+    /// if there are no elements.
+    fn value_statistics( &self ) -> Option<ValueStats> where T: Into<f64> + Copy {
+        let first : f64 = self.first()?.val.into();
+        let mut stats   = ValueStats{ count: 0, sum: 0.0, mean: 0.0, min: first, max: first };
+        for o in self.iter() {
+            let val : f64 = o.val.into();
+            stats.count += 1;
+            stats.sum   += val;
+            stats.min    = stats.min.min( val );
+            stats.max    = stats.max.max( val );
+        }
+        stats.mean = stats.sum / stats.count as f64;
+        Some( stats )
+    }
+    /// Remove every element whose value equals the value of an element earlier in hash order,
+    /// so that only the lowest hash key is kept for each distinct value. As T is only
+    /// PartialEq this compares every pair of values.
+    fn dedup_by_value( &mut self ) where T: PartialEq {
+        let keep : Vec<bool> = ( 0..self.len() )
+            .map( |i| !self[..i].iter().any( |o| o.val == self[i].val ) )
+            .collect();
+        let mut keep = keep.into_iter();
+        self.retain( |_| keep.next().unwrap_or( true ) );
+        debug_check_invariant( self );
+    }
+    /// Return a reference to the value identified by the key _key_ or, if missing, by the most
+    /// specific wildcard key above it. For `a.b.c` with `.` as _separator_ the keys `a.b.c`,
+    /// `a.b.*`, `a.*` and `*` are tried in this order.
+    fn get_with_fallback( &self, key : &str, separator : char ) -> Option<&T> {
+        if let Some( val ) = self.get( key ) {
+            return Some( val );
+        }
+        let mut prefix = key;
+        while let Some( pos ) = prefix.rfind( separator ) {
+            prefix = &prefix[..pos];
+            if let Some( val ) = self.get( &format!( "{}{}*", prefix, separator ) ) {
+                return Some( val );
+            }
+        }
+        self.get( "*" )
+    }
 }
 
 impl<T> Hash for DictEntry<T> {
@@ -109,6 +993,185 @@ impl<T> Hash for DictEntry<T> {
     }
 }
 
+/// Dict storing its values boxed. Inserting in the middle of the vector then only shifts
+/// pointers instead of whole values, which pays off for large T at the cost of an extra
+/// indirection on every read.
+pub type BoxedDict<T> = Dict<Box<T>>;
+
+pub trait BoxedDictIface<T> {
+    fn add_boxed( &mut self, key : String, val : T ) -> bool;
+    fn get_unboxed( &self, key : &str ) -> Option<&T>;
+}
+
+impl<T> BoxedDictIface<T> for BoxedDict<T> {
+    /// Box _val_ and add it indexed by the string _key_. Returns false if the key exists or there
+    /// is a hash collision
+    fn add_boxed( &mut self, key : String, val : T ) -> bool {
+        self.add( key, Box::new( val ) )
+    }
+    /// Return a reference to the unboxed value identified by the key _key_, if exists.
+    fn get_unboxed( &self, key : &str ) -> Option<&T> {
+        self.get( key ).map( |o| &**o )
+    }
+}
+
+/// Return the hash under which _key_ is stored.
+pub fn hash_of( key : &str ) -> u64 {
+    hash_f( key )
+}
+
+/// Return the approximate probability that at least two of _n_ distinct keys share the same
+/// 64-bit hash, in which case the second one is rejected by add(). Uses the birthday paradox
+/// approximation `1 - exp( -n(n-1)/2 / 2^64 )`.
+pub fn collision_probability( n : usize ) -> f64 {
+    let n     = n as f64;
+    let pairs = n * ( n - 1.0 ) / 2.0;
+    -( -pairs / 2f64.powi( 64 ) ).exp_m1()
+}
+
+/// Sort the elements by hash, dropping all but the last of those sharing a hash.
+fn sort_last_wins<T>( dict : &mut Dict<T> ) {
+    dict.sort_by_key( |o| o.hash );
+    dict.dedup_by( |later, kept| {
+        if later.hash != kept.hash {
+            return false;
+        }
+        ::std::mem::swap( later, kept );
+        true
+    } );
+    debug_check_invariant( dict );
+}
+
+/// Assert that the elements are strictly increasing by hash, which also rules out duplicate keys.
+/// Only compiled in debug builds.
+#[cfg(debug_assertions)]
+fn debug_check_invariant<T>( dict : &Dict<T> ) {
+    assert!( dict.is_hash_sorted(), "Dict invariant violated: elements are not strictly sorted by hash" );
+}
+
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+fn debug_check_invariant<T>( _dict : &Dict<T> ) {}
+
+/// Assert that _hashes_ are strictly increasing, for the dictionaries using their own entry type.
+/// Only compiled in debug builds.
+#[cfg(debug_assertions)]
+fn debug_check_hashes<I: Iterator<Item = u64>>( hashes : I ) {
+    let mut prev : Option<u64> = None;
+    for hash in hashes {
+        assert!( prev.is_none_or( |p| p < hash ), "Dict invariant violated: elements are not strictly sorted by hash" );
+        prev = Some( hash );
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+fn debug_check_hashes<I: Iterator<Item = u64>>( _hashes : I ) {}
+
+/// Return the number of single char insertions, deletions or substitutions needed to turn _a_
+/// into _b_.
+fn levenshtein( a : &str, b : &str ) -> usize {
+    let b : Vec<char> = b.chars().collect();
+    let mut row : Vec<usize> = ( 0..=b.len() ).collect();
+    for ( i, ca ) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for ( j, cb ) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb { diag } else { 1 + diag.min( above ).min( row[j] ) };
+            diag = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Return true if _text_ matches _pattern_, where `*` matches any sequence of chars and `?` any
+/// single char.
+fn glob_match( pattern : &[char], text : &str ) -> bool {
+    let text : Vec<char> = text.chars().collect();
+    let ( mut p, mut t ) = ( 0, 0 );
+    let mut backtrack    = None;   // position of the last `*` and of the text it consumed up to
+    while t < text.len() {
+        // check for `*` first, or it would match a literal `*` in the text
+        if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some( ( p, t ) );
+            p += 1;
+        } else if p < pattern.len() && ( pattern[p] == '?' || pattern[p] == text[t] ) {
+            p += 1;
+            t += 1;
+        } else if let Some( ( star, consumed ) ) = backtrack {
+            backtrack = Some( ( star, consumed + 1 ) );
+            p = star + 1;
+            t = consumed + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all( |&c| c == '*' )
+}
+
+/// Return _s_ as is if it only contains chars that are safe in a shell word, or single quoted
+/// otherwise.
+fn shell_quote( s : &str ) -> String {
+    let safe = |c : char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains( c );
+    if !s.is_empty() && s.chars().all( safe ) {
+        s.to_string()
+    } else {
+        format!( "'{}'", s.replace( '\'', "'\\''" ) )
+    }
+}
+
+/// Return _s_ with every byte other than ASCII letters, digits and `-._~` written as `%XX`.
+fn percent_encode( s : &str ) -> String {
+    let mut out = String::with_capacity( s.len() );
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains( &b ) {
+            out.push( b as char );
+        } else {
+            out.push_str( &format!( "%{:02X}", b ) );
+        }
+    }
+    out
+}
+
+/// Return _s_ with every `%XX` escape decoded and `+` replaced by a space, or None if an escape
+/// is malformed or the result is not valid UTF-8.
+fn percent_decode( s : &str ) -> Option<String> {
+    let mut bytes = Vec::with_capacity( s.len() );
+    let mut iter  = s.bytes();
+    while let Some( b ) = iter.next() {
+        match b {
+            b'%' => {
+                let hi = ( iter.next()? as char ).to_digit( 16 )?;
+                let lo = ( iter.next()? as char ).to_digit( 16 )?;
+                bytes.push( ( hi * 16 + lo ) as u8 );
+            },
+            b'+' => bytes.push( b' ' ),
+            _    => bytes.push( b ),
+        }
+    }
+    String::from_utf8( bytes ).ok()
+}
+
+/// Return _s_ as a double quoted JSON string.
+fn json_quote( s : &str ) -> String {
+    let mut out = String::with_capacity( s.len() + 2 );
+    out.push( '"' );
+    for c in s.chars() {
+        match c {
+            '"'  => out.push_str( "\\\"" ),
+            '\\' => out.push_str( "\\\\" ),
+            '\n' => out.push_str( "\\n" ),
+            '\r' => out.push_str( "\\r" ),
+            '\t' => out.push_str( "\\t" ),
+            c if ( c as u32 ) < 0x20 => out.push_str( &format!( "\\u{:04x}", c as u32 ) ),
+            c    => out.push( c ),
+        }
+    }
+    out.push( '"' );
+    out
+}
+
 fn hash_f<T>(obj: T) -> u64
 where
     T: Hash,
@@ -118,6 +1181,1094 @@ where
     hasher.finish()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict_of<T>( pairs : Vec<(&str, T)> ) -> Dict<T> {
+        let mut dict = Dict::new();
+        for ( key, val ) in pairs {
+            dict.add( key.to_string(), val );
+        }
+        dict
+    }
+
+    fn sorted_by_hash<T>( dict : &Dict<T> ) -> bool {
+        dict.windows( 2 ).all( |w| w[0].hash < w[1].hash )
+    }
+
+    // dict mapping "k0", "k1"... to 0, 1...
+    fn numbered( range : ::std::ops::Range<i32> ) -> Dict<i32> {
+        let mut dict = Dict::new();
+        for i in range {
+            dict.add( format!( "k{}", i ), i );
+        }
+        dict
+    }
+
+    #[test]
+    fn total_key_bytes_counts_utf8_bytes() {
+        let dict = dict_of( vec![ ( "ab", 1 ), ( "ñé", 2 ), ( "", 3 ) ] );
+        assert_eq!( dict.total_key_bytes(), "ab".len() + "ñé".len() );
+        assert_eq!( dict.total_key_bytes(), 6 );
+        assert_eq!( Dict::<i32>::new().total_key_bytes(), 0 );
+    }
+
+    #[test]
+    fn replace_contents_drops_old_keys() {
+        let mut dict = dict_of( vec![ ( "old", 1 ), ( "x", 1 ) ] );
+        dict.replace_contents( vec![ ( "a".to_string(), 1 ), ( "b".to_string(), 2 ), ( "a".to_string(), 3 ), ( "c".to_string(), 4 ) ] );
+        assert!( !dict.contains_key( "old" ) );
+        assert!( !dict.contains_key( "x" ) );
+        assert_eq!( dict.len(), 3 );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+        assert_eq!( dict.get( "b" ), Some( &2 ) );
+        assert_eq!( dict.get( "c" ), Some( &4 ) );
+        assert!( sorted_by_hash( &dict ) );
+    }
+
+    #[test]
+    fn values_contiguous_lines_up_with_entry_slice() {
+        let dict  = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 3 ) ] );
+        let slice = dict.entry_slice();
+        assert_eq!( slice.len(), dict.len() );
+        let vals  = dict.values_contiguous();
+        assert_eq!( vals.len(), slice.len() );
+        for ( entry, val ) in slice.iter().zip( vals ) {
+            assert_eq!( &entry.val, val );
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invariant violated")]
+    fn add_panics_on_corrupted_order() {
+        let mut dict = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 3 ) ] );
+        dict.reverse();
+        dict.add( "d".to_string(), 4 );
+    }
+
+    #[test]
+    fn interpolate_substitutes_known_keys() {
+        let dict = dict_of( vec![ ( "host", "h".to_string() ), ( "port", "80".to_string() ) ] );
+        assert_eq!( dict.interpolate( "http://${host}:${port}/" ), "http://h:80/" );
+        assert_eq!( dict.interpolate( "ñ${host}ñ" ), "ñhñ" );
+    }
+
+    #[test]
+    fn interpolate_leaves_unknown_and_malformed_placeholders() {
+        let dict = dict_of( vec![ ( "host", "h".to_string() ) ] );
+        assert_eq!( dict.interpolate( "${nope} ${}" ), "${nope} ${}" );
+        assert_eq!( dict.interpolate( "$$host $x ${host" ), "$host $x ${host" );
+        assert_eq!( dict.interpolate( "trailing $" ), "trailing $" );
+    }
+
+    #[test]
+    fn interpolate_keeps_empty_placeholder_with_empty_key() {
+        let dict = dict_of( vec![ ( "", "EMPTY".to_string() ) ] );
+        assert_eq!( dict.interpolate( "x${}y" ), "x${}y" );
+    }
+
+    #[test]
+    fn matches_hashmap_compares_pairs() {
+        let dict    = dict_of( vec![ ( "a", 1 ), ( "b", 2 ) ] );
+        let mut map = HashMap::new();
+        map.insert( "a".to_string(), 1 );
+        map.insert( "b".to_string(), 2 );
+        assert!( dict.matches_hashmap( &map ) );
+        map.insert( "b".to_string(), 3 );
+        assert!( !dict.matches_hashmap( &map ) );
+        map.insert( "b".to_string(), 2 );
+        map.insert( "c".to_string(), 2 );
+        assert!( !dict.matches_hashmap( &map ) );
+        assert!( !Dict::<i32>::new().matches_hashmap( &map ) );
+    }
+
+    #[test]
+    fn group_by_value_buckets_keys() {
+        let dict   = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 3 ), ( "d", 4 ) ] );
+        let groups = dict.group_by_value( |v| v % 2 == 0 );
+        let mut even : Vec<&str> = groups[&true].iter().map( |k| k.as_str() ).collect();
+        let mut odd  : Vec<&str> = groups[&false].iter().map( |k| k.as_str() ).collect();
+        even.sort();
+        odd.sort();
+        assert_eq!( even, vec![ "b", "d" ] );
+        assert_eq!( odd, vec![ "a", "c" ] );
+    }
+
+    #[test]
+    fn get_mut_mutates_in_place() {
+        let mut dict = dict_of( vec![ ( "k", 1 ) ] );
+        *dict.get_mut( "k" ).unwrap() += 1;
+        assert_eq!( dict.get( "k" ), Some( &2 ) );
+        assert!( dict.get_mut( "missing" ).is_none() );
+        assert_eq!( dict.len(), 1 );
+    }
+
+    #[test]
+    fn remove_first_by_value_removes_lowest_hash_match() {
+        let mut dict = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 1 ) ] );
+        let first    = dict.iter().find( |o| o.val == 1 ).unwrap().key.clone();
+        assert_eq!( dict.remove_first_by_value( &1 ), Some( ( first, 1 ) ) );
+        assert_eq!( dict.len(), 2 );
+        assert_eq!( dict.remove_first_by_value( &9 ), None );
+        assert_eq!( dict.len(), 2 );
+        assert!( dict.remove_first_by_value( &1 ).is_some() );
+        assert_eq!( dict.remove_first_by_value( &1 ), None );
+        assert_eq!( dict.get( "b" ), Some( &2 ) );
+    }
+
+    #[test]
+    fn sort_by_hash_restores_order() {
+        let mut dict = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 3 ), ( "d", 4 ), ( "e", 5 ) ] );
+        dict.reverse();
+        dict.swap( 0, 2 );
+        assert!( !sorted_by_hash( &dict ) );
+        dict.sort_by_hash();
+        assert!( sorted_by_hash( &dict ) );
+        for key in &[ "a", "b", "c", "d", "e" ] {
+            assert!( dict.contains_key( key ) );
+        }
+        assert!( dict.add( "f".to_string(), 6 ) );
+    }
+
+    #[test]
+    fn collision_probability_grows_from_zero() {
+        assert_eq!( collision_probability( 0 ), 0.0 );
+        assert_eq!( collision_probability( 1 ), 0.0 );
+        assert!( collision_probability( 1000 ) < 1e-12 );
+        let mut prev = 0.0;
+        for &n in &[ 10usize, 1000, 1_000_000, 1 << 32, 1 << 34 ] {
+            let p = collision_probability( n );
+            assert!( p > prev );
+            prev = p;
+        }
+        // 2^32 keys give 1 - e^-0.5
+        assert!( ( collision_probability( 1 << 32 ) - 0.3935 ).abs() < 1e-3 );
+    }
+
+    #[test]
+    fn boxed_dict_round_trips_large_values() {
+        #[derive(Debug, PartialEq)]
+        struct Big( [u64; 64] );
+        let mut dict = BoxedDict::<Big>::new();
+        assert!( dict.add_boxed( "a".to_string(), Big( [7; 64] ) ) );
+        assert!( !dict.add_boxed( "a".to_string(), Big( [1; 64] ) ) );
+        assert_eq!( dict.get_unboxed( "a" ), Some( &Big( [7; 64] ) ) );
+        assert!( dict.get_unboxed( "b" ).is_none() );
+    }
+
+    #[test]
+    fn iter_sorted_by_key_is_lexical() {
+        let keys = vec![ "q", "b", "zz", "a", "m" ];
+        let dict = dict_of( keys.iter().enumerate().map( |( i, k )| ( *k, i ) ).collect() );
+        let got : Vec<&str> = dict.iter_sorted_by_key().map( |( k, _ )| k.as_str() ).collect();
+        let mut expected    = keys.clone();
+        expected.sort();
+        assert_eq!( got, expected );
+        assert_eq!( dict.iter_sorted_by_key().next(), Some( ( &"a".to_string(), &3 ) ) );
+    }
+
+    #[test]
+    fn key_first_char_histogram_counts_first_chars() {
+        let dict      = dict_of( vec![ ( "apple", 0 ), ( "avocado", 0 ), ( "banana", 0 ), ( "", 0 ), ( "ñu", 0 ) ] );
+        let histogram = dict.key_first_char_histogram();
+        assert_eq!( histogram.len(), 3 );
+        assert_eq!( histogram[&'a'], 2 );
+        assert_eq!( histogram[&'b'], 1 );
+        assert_eq!( histogram[&'ñ'], 1 );
+    }
+
+    #[test]
+    fn remove_key_required_returns_value() {
+        let mut dict = dict_of( vec![ ( "a", 1 ) ] );
+        assert_eq!( dict.remove_key_required( "a" ), Ok( 1 ) );
+        assert!( dict.is_empty() );
+    }
+
+    #[test]
+    fn remove_key_required_names_missing_key() {
+        let mut dict = dict_of( vec![ ( "b", 2 ) ] );
+        let err      = dict.remove_key_required( "zz" ).unwrap_err();
+        assert_eq!( err, KeyNotFound{ key: "zz".to_string() } );
+        assert_eq!( err.to_string(), "key not found: zz" );
+        assert_eq!( dict.len(), 1 );
+    }
+
+    #[test]
+    fn try_map_values_converts_every_value() {
+        let dict   = dict_of( vec![ ( "a", "1".to_string() ), ( "b", "2".to_string() ) ] );
+        let parsed = dict.try_map_values( |v| v.parse::<i32>() ).unwrap();
+        assert_eq!( parsed.get( "a" ), Some( &1 ) );
+        assert_eq!( parsed.get( "b" ), Some( &2 ) );
+    }
+
+    #[test]
+    fn try_map_values_stops_at_error() {
+        let dict = dict_of( vec![ ( "a", "1".to_string() ), ( "b", "x".to_string() ) ] );
+        assert!( dict.try_map_values( |v| v.parse::<i32>() ).is_err() );
+    }
+
+    #[test]
+    fn near_duplicate_keys_ignores_case_and_whitespace() {
+        let dict      = dict_of( vec![ ( "Host", 0 ), ( "host ", 0 ), ( "port", 0 ), ( "Port", 0 ), ( " other", 0 ) ] );
+        let mut pairs = dict.near_duplicate_keys();
+        for pair in pairs.iter_mut() {
+            if pair.0 > pair.1 {
+                ::std::mem::swap( &mut pair.0, &mut pair.1 );
+            }
+        }
+        pairs.sort();
+        assert_eq!( pairs, vec![ ( "Host".to_string(), "host ".to_string() ),
+                                 ( "Port".to_string(), "port".to_string() ) ] );
+    }
+
+    fn hashed_tuples( n : i32 ) -> Vec<(u64, String, i32)> {
+        let mut tuples : Vec<(u64, String, i32)> = ( 0..n ).map( |i| {
+            let key = format!( "k{}", i );
+            ( hash_f( &key ), key, i )
+        } ).collect();
+        tuples.sort_by_key( |t| t.0 );
+        tuples
+    }
+
+    #[test]
+    fn extend_sorted_into_empty_dict() {
+        let mut dict = Dict::new();
+        dict.extend_sorted( hashed_tuples( 20 ) );
+        assert_eq!( dict.len(), 20 );
+        assert!( sorted_by_hash( &dict ) );
+        for i in 0..20 {
+            assert_eq!( dict.get( &format!( "k{}", i ) ), Some( &i ) );
+        }
+    }
+
+    #[test]
+    fn extend_sorted_into_populated_dict() {
+        let mut dict = dict_of( vec![ ( "k3", 100 ), ( "zz", 5 ) ] );
+        dict.extend_sorted( hashed_tuples( 20 ) );
+        assert_eq!( dict.len(), 21 );
+        assert!( sorted_by_hash( &dict ) );
+        // existing elements win, like add()
+        assert_eq!( dict.get( "k3" ), Some( &100 ) );
+        assert_eq!( dict.get( "k4" ), Some( &4 ) );
+        assert_eq!( dict.get( "zz" ), Some( &5 ) );
+    }
+
+    #[test]
+    fn closest_keys_suggests_typo_fixes() {
+        let dict = dict_of( vec![ ( "host", 0 ), ( "port", 0 ), ( "hostname", 0 ), ( "user", 0 ) ] );
+        assert_eq!( dict.closest_keys( "hots", 2 ), vec![ "host" ] );
+        assert_eq!( dict.closest_keys( "hostnam", 4 ), vec![ "hostname", "host" ] );
+        assert!( dict.closest_keys( "zzzzzz", 2 ).is_empty() );
+    }
+
+    #[test]
+    fn closest_keys_uses_levenshtein_distance() {
+        let dict = dict_of( vec![ ( "sitting", 0 ) ] );
+        assert!( dict.closest_keys( "kitten", 2 ).is_empty() );
+        assert_eq!( dict.closest_keys( "kitten", 3 ), vec![ "sitting" ] );
+    }
+
+    #[test]
+    fn chunks_partition_entries() {
+        let dict   = numbered( 0..10 );
+        let chunks = dict.chunks( 3 );
+        assert_eq!( chunks.iter().map( |c| c.len() ).collect::<Vec<usize>>(), vec![ 4, 3, 3 ] );
+        let keys : Vec<&String> = chunks.iter().flat_map( |c| c.iter().map( |o| &o.key ) ).collect();
+        assert_eq!( keys, dict.iter().map( |o| &o.key ).collect::<Vec<&String>>() );
+        for chunk in &chunks {
+            assert!( sorted_by_hash( chunk ) );
+        }
+    }
+
+    #[test]
+    fn chunks_handles_zero_and_too_many() {
+        let dict = dict_of( vec![ ( "a", 1 ), ( "b", 2 ) ] );
+        assert!( dict.chunks( 0 ).is_empty() );
+        let chunks = dict.chunks( 4 );
+        assert_eq!( chunks.len(), 4 );
+        assert_eq!( chunks.iter().filter( |c| c.is_empty() ).count(), 2 );
+    }
+
+    #[test]
+    fn swap_with_exchanges_contents() {
+        let mut a = dict_of( vec![ ( "a", 1 ) ] );
+        let mut b = dict_of( vec![ ( "b", 2 ), ( "c", 3 ) ] );
+        a.swap_with( &mut b );
+        assert_eq!( a.len(), 2 );
+        assert_eq!( a.get( "c" ), Some( &3 ) );
+        assert_eq!( b.len(), 1 );
+        assert_eq!( b.get( "a" ), Some( &1 ) );
+    }
+
+    #[test]
+    fn normalize_keys_trims_keys() {
+        let mut dict = dict_of( vec![ ( " a", 1 ), ( "b ", 2 ), ( "c", 3 ) ] );
+        dict.normalize_keys( |k| k.trim().to_string() );
+        assert_eq!( dict.len(), 3 );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+        assert_eq!( dict.get( "b" ), Some( &2 ) );
+        assert!( sorted_by_hash( &dict ) );
+    }
+
+    #[test]
+    fn normalize_keys_collision_is_last_wins() {
+        let mut dict = dict_of( vec![ ( " a", 1 ), ( "a", 2 ) ] );
+        // last in hash order before normalizing
+        let last     = dict.iter().last().unwrap().val;
+        dict.normalize_keys( |k| k.trim().to_string() );
+        assert_eq!( dict.len(), 1 );
+        assert_eq!( dict.get( "a" ), Some( &last ) );
+    }
+
+    #[test]
+    fn into_btreemap_round_trip() {
+        let dict = dict_of( vec![ ( "b", 2 ), ( "a", 1 ), ( "c", 3 ) ] );
+        let map  = dict.into_btreemap();
+        assert_eq!( map.keys().map( |k| k.as_str() ).collect::<Vec<&str>>(), vec![ "a", "b", "c" ] );
+        let mut dict = Dict::new();
+        for ( key, val ) in map {
+            dict.add( key, val );
+        }
+        assert_eq!( dict.len(), 3 );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+        assert_eq!( dict.get( "c" ), Some( &3 ) );
+    }
+
+    #[test]
+    fn changed_since_reports_shared_changed_keys() {
+        let baseline = numbered( 0..10 );
+        let mut now  = numbered( 5..15 );
+        *now.get_mut( "k6" ).unwrap() = 60;
+        *now.get_mut( "k8" ).unwrap() = 80;
+        *now.get_mut( "k12" ).unwrap() = 120;
+        let mut changed : Vec<(&String, &i32, &i32)> = now.changed_since( &baseline );
+        changed.sort();
+        assert_eq!( changed, vec![ ( &"k6".to_string(), &6, &60 ), ( &"k8".to_string(), &8, &80 ) ] );
+        assert!( now.changed_since( &now ).is_empty() );
+    }
+
+    #[test]
+    fn write_to_read_from_round_trip() {
+        let dict    = dict_of( vec![ ( "a", "x".to_string() ), ( "ñ", "".to_string() ), ( "", "yy".to_string() ) ] );
+        let mut buf = io::Cursor::new( Vec::new() );
+        dict.write_to( &mut buf ).unwrap();
+        buf.set_position( 0 );
+        let read = Dict::<String>::read_from( &mut buf ).unwrap();
+        assert_eq!( read.len(), 3 );
+        for o in &dict {
+            assert_eq!( read.get( &o.key ), Some( &o.val ) );
+        }
+        assert!( sorted_by_hash( &read ) );
+    }
+
+    #[test]
+    fn read_from_rejects_truncated_input() {
+        let dict      = dict_of( vec![ ( "a", "x".to_string() ) ] );
+        let mut bytes = Vec::new();
+        dict.write_to( &mut bytes ).unwrap();
+        bytes.pop();
+        let err = Dict::<String>::read_from( &mut io::Cursor::new( bytes ) ).err().unwrap();
+        assert_eq!( err.kind(), io::ErrorKind::UnexpectedEof );
+    }
+
+    #[test]
+    fn bulk_increment_updates_and_inserts() {
+        let mut dict = dict_of( vec![ ( "a", 1u32 ), ( "b", 2 ) ] );
+        dict.bulk_increment( &[ ( "a", 1 ), ( "c", 5 ), ( "b", 1 ), ( "c", 1 ), ( "d", 0 ), ( "a", 3 ) ] );
+        assert_eq!( dict.len(), 4 );
+        assert_eq!( dict.get( "a" ), Some( &5 ) );
+        assert_eq!( dict.get( "b" ), Some( &3 ) );
+        assert_eq!( dict.get( "c" ), Some( &6 ) );
+        assert_eq!( dict.get( "d" ), Some( &0 ) );
+        assert!( sorted_by_hash( &dict ) );
+    }
+
+    #[test]
+    fn key_at_and_value_at_follow_hash_order() {
+        let dict = dict_of( vec![ ( "a", 1 ), ( "b", 2 ) ] );
+        let key  = dict.key_at( 1 ).unwrap();
+        assert_eq!( key, &dict[1].key );
+        assert_eq!( dict.value_at( 1 ), dict.get( key ) );
+        assert!( dict.key_at( 2 ).is_none() );
+        assert!( dict.value_at( 9 ).is_none() );
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn random_entry_is_deterministic_for_a_seed() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        assert!( Dict::<i32>::new().random_entry( &mut StdRng::seed_from_u64( 7 ) ).is_none() );
+        let dict     = numbered( 0..10 );
+        let mut rng1 = StdRng::seed_from_u64( 7 );
+        let mut rng2 = StdRng::seed_from_u64( 7 );
+        let picks1 : Vec<&String> = ( 0..20 ).map( |_| dict.random_entry( &mut rng1 ).unwrap().0 ).collect();
+        let picks2 : Vec<&String> = ( 0..20 ).map( |_| dict.random_entry( &mut rng2 ).unwrap().0 ).collect();
+        assert_eq!( picks1, picks2 );
+        for key in picks1 {
+            assert!( dict.contains_key( key ) );
+        }
+    }
+
+    #[test]
+    fn iter_with_hash_yields_stored_hashes() {
+        let dict = numbered( 0..10 );
+        assert_eq!( dict.iter_with_hash().count(), 10 );
+        for ( ( hash, key, val ), o ) in dict.iter_with_hash().zip( dict.iter() ) {
+            assert_eq!( hash, hash_of( key ) );
+            assert_eq!( hash, o.hash );
+            assert_eq!( val, &o.val );
+        }
+    }
+
+    #[test]
+    fn content_hash_ignores_insertion_order() {
+        let a     = numbered( 0..10 );
+        let mut b = Dict::new();
+        for i in ( 0..10 ).rev() {
+            b.add( format!( "k{}", i ), i );
+        }
+        assert_eq!( a.content_hash(), b.content_hash() );
+        *b.get_mut( "k3" ).unwrap() = 99;
+        assert_ne!( a.content_hash(), b.content_hash() );
+    }
+
+    #[test]
+    fn add_validated_accepts_clean_keys() {
+        let mut dict = Dict::new();
+        let allowed  = |c : char| c != '=' && !c.is_whitespace();
+        assert_eq!( dict.add_validated( "a.b".to_string(), 1, &allowed ), Ok( true ) );
+        assert_eq!( dict.add_validated( "a.b".to_string(), 2, &allowed ), Ok( false ) );
+        assert_eq!( dict.get( "a.b" ), Some( &1 ) );
+    }
+
+    #[test]
+    fn add_validated_names_disallowed_char() {
+        let mut dict = Dict::new();
+        let allowed  = |c : char| c != '=' && !c.is_whitespace();
+        let err      = dict.add_validated( "a=b".to_string(), 1, &allowed ).unwrap_err();
+        assert_eq!( err, InvalidKey{ key: "a=b".to_string(), ch: '=' } );
+        assert_eq!( err.to_string(), "invalid character '=' in key: a=b" );
+        assert!( dict.is_empty() );
+    }
+
+    #[test]
+    fn alist_round_trip() {
+        let dict  = numbered( 0..5 );
+        let alist = dict.to_alist();
+        assert_eq!( alist.len(), 5 );
+        assert_eq!( alist[0].0, dict[0].key );
+        let back = Dict::from_alist( alist.clone() );
+        assert_eq!( back.to_alist(), alist );
+        assert!( sorted_by_hash( &back ) );
+    }
+
+    #[test]
+    fn load_kv_lines_skips_comments_and_splits_on_first_equal() {
+        let mut dict = Dict::<String>::new();
+        let input    = "# comment\nhost = example.com\n\n  # indented comment\nquery=a=b&c=d\nhost=dup\n";
+        assert_eq!( dict.load_kv_lines( input.as_bytes() ).unwrap(), 2 );
+        assert_eq!( dict.len(), 2 );
+        assert_eq!( dict.get( "host" ).map( |v| v.as_str() ), Some( "example.com" ) );
+        assert_eq!( dict.get( "query" ).map( |v| v.as_str() ), Some( "a=b&c=d" ) );
+    }
+
+    #[test]
+    fn load_kv_lines_keeps_lines_before_error() {
+        let mut dict = Dict::<u16>::new();
+        let err      = dict.load_kv_lines( "port=80\nbad=x\nlate=1\n".as_bytes() ).unwrap_err();
+        assert_eq!( err.kind(), io::ErrorKind::InvalidData );
+        assert!( err.to_string().contains( "line 2" ) );
+        assert_eq!( dict.get( "port" ), Some( &80 ) );
+        assert!( !dict.contains_key( "late" ) );
+        assert!( dict.load_kv_lines( "noequals\n".as_bytes() ).is_err() );
+        assert_eq!( dict.len(), 1 );
+    }
+
+    #[test]
+    fn is_subset_compares_pairs() {
+        let small   = numbered( 0..5 );
+        let mut big = numbered( 0..10 );
+        assert!( small.is_subset( &big ) );
+        assert!( !big.is_subset( &small ) );
+        assert!( small.is_subset( &small ) );
+        assert!( Dict::new().is_subset( &small ) );
+        *big.get_mut( "k2" ).unwrap() = 7;
+        assert!( !small.is_subset( &big ) );
+    }
+
+    fn glob_keys( dict : &Dict<i32>, pattern : &str ) -> Vec<String> {
+        let mut keys : Vec<String> = dict.entries_matching_glob( pattern ).into_iter().map( |( k, _ )| k.clone() ).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn entries_matching_glob_wildcards() {
+        let dict = dict_of( vec![ ( "db.host", 0 ), ( "db.port", 0 ), ( "dbx", 0 ), ( "web.host", 0 ), ( "a", 0 ), ( "ab", 0 ), ( "", 0 ) ] );
+        assert_eq!( glob_keys( &dict, "db.*" ), vec![ "db.host", "db.port" ] );
+        assert_eq!( glob_keys( &dict, "*.host" ), vec![ "db.host", "web.host" ] );
+        assert_eq!( glob_keys( &dict, "?" ), vec![ "a" ] );
+        assert_eq!( glob_keys( &dict, "d?.*t" ), vec![ "db.host", "db.port" ] );
+        assert_eq!( glob_keys( &dict, "*b*o*" ), vec![ "db.host", "db.port", "web.host" ] );
+        assert_eq!( glob_keys( &dict, "**a?" ), vec![ "ab" ] );
+        assert_eq!( glob_keys( &dict, "*" ).len(), dict.len() );
+        assert!( glob_keys( &dict, "x*" ).is_empty() );
+    }
+
+    #[test]
+    fn entries_matching_glob_literals() {
+        let dict = dict_of( vec![ ( "a", 0 ), ( "ab", 0 ), ( "", 0 ) ] );
+        assert_eq!( glob_keys( &dict, "a" ), vec![ "a" ] );
+        assert_eq!( glob_keys( &dict, "" ), vec![ "" ] );
+    }
+
+    #[test]
+    fn entries_matching_glob_keys_with_stars() {
+        let dict = dict_of( vec![ ( "a.*x", 0 ), ( "*a", 0 ), ( "a.*", 0 ) ] );
+        assert_eq!( glob_keys( &dict, "a.*" ), vec![ "a.*", "a.*x" ] );
+        assert_eq!( glob_keys( &dict, "*" ), vec![ "*a", "a.*", "a.*x" ] );
+        assert_eq!( glob_keys( &dict, "*a" ), vec![ "*a" ] );
+        assert_eq!( glob_keys( &dict, "?a" ), vec![ "*a" ] );
+    }
+
+    #[test]
+    fn transaction_applies_changes_on_success() {
+        let mut dict = dict_of( vec![ ( "a", 1 ) ] );
+        let result   = dict.transaction( |d| {
+            d.add( "b".to_string(), 2 );
+            *d.get_mut( "a" ).unwrap() = 5;
+            Ok::<(), ()>( () )
+        } );
+        assert_eq!( result, Ok( () ) );
+        assert_eq!( dict.get( "a" ), Some( &5 ) );
+        assert_eq!( dict.get( "b" ), Some( &2 ) );
+    }
+
+    #[test]
+    fn transaction_rolls_back_on_error() {
+        let mut dict = dict_of( vec![ ( "a", 1 ) ] );
+        let result   = dict.transaction( |d| {
+            d.add( "c".to_string(), 3 );
+            d.remove_key( "a" );
+            Err( "boom" )
+        } );
+        assert_eq!( result, Err( "boom" ) );
+        assert_eq!( dict.len(), 1 );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+        assert!( !dict.contains_key( "c" ) );
+    }
+
+    #[test]
+    fn to_env_string_mangles_and_quotes() {
+        let dict = dict_of( vec![ ( "db.host", "example.com".to_string() ), ( "msg", "it's a test".to_string() ), ( "empty", "".to_string() ) ] );
+        assert_eq!( dict.to_env_string( "app" ), "APP_DB_HOST=example.com\nAPP_EMPTY=''\nAPP_MSG='it'\\''s a test'\n" );
+        assert_eq!( dict.to_env_string( "" ), "DB_HOST=example.com\nEMPTY=''\nMSG='it'\\''s a test'\n" );
+    }
+
+    #[test]
+    fn to_env_string_collisions_and_leading_digits() {
+        let dict = dict_of( vec![ ( "db.host", 1 ), ( "DB_HOST", 2 ), ( "1a", 3 ), ( "", 4 ) ] );
+        assert_eq!( dict.to_env_string( "" ), "DB_HOST=1\n_=4\n_1A=3\n" );
+        assert_eq!( dict.to_env_string( "9" ), "_9_=4\n_9_1A=3\n_9_DB_HOST=1\n" );
+    }
+
+    #[test]
+    fn key_union_overlapping_sets() {
+        let union = numbered( 0..6 ).key_union( &numbered( 4..9 ) );
+        assert_eq!( union.len(), 9 );
+        assert!( union.windows( 2 ).all( |w| hash_f( &w[0] ) < hash_f( &w[1] ) ) );
+        let mut dedup = union.clone();
+        dedup.sort();
+        dedup.dedup();
+        assert_eq!( dedup.len(), union.len() );
+    }
+
+    #[test]
+    fn key_union_disjoint_sets() {
+        let other = dict_of( vec![ ( "x", 0 ) ] );
+        assert_eq!( numbered( 0..6 ).key_union( &other ).len(), 7 );
+        assert_eq!( Dict::new().key_union( &other ), vec![ "x" ] );
+    }
+
+    #[test]
+    fn entry_size_covers_hash_key_and_value() {
+        use std::mem::size_of;
+
+        assert!( Dict::<u8>::entry_size() >= size_of::<u8>() + size_of::<u64>() + size_of::<String>() );
+        assert!( Dict::<[u64; 4]>::entry_size() >= size_of::<[u64; 4]>() + size_of::<u64>() + size_of::<String>() );
+    }
+
+    #[test]
+    fn try_for_each_value_mut_applies_to_all() {
+        let mut dict = numbered( 0..5 );
+        assert_eq!( dict.try_for_each_value_mut( |v| { *v *= 2; Ok::<(), i32>( () ) } ), Ok( () ) );
+        assert_eq!( dict.get( "k4" ), Some( &8 ) );
+    }
+
+    #[test]
+    fn try_for_each_value_mut_stops_at_error() {
+        let mut dict  = numbered( 0..5 );
+        let mut calls = 0;
+        let result    = dict.try_for_each_value_mut( |v| {
+            calls += 1;
+            if *v == 2 { Err( *v ) } else { *v += 10; Ok( () ) }
+        } );
+        assert_eq!( result, Err( 2 ) );
+        // values after the failing one in hash order are left untouched
+        let failed = dict.iter().position( |o| o.key == "k2" ).unwrap();
+        assert_eq!( calls, failed + 1 );
+        for ( i, o ) in dict.iter().enumerate() {
+            let original : i32 = o.key[1..].parse().unwrap();
+            assert_eq!( o.val, if i < failed { original + 10 } else { original } );
+        }
+    }
+
+    #[test]
+    fn into_columns_keeps_pairs_aligned() {
+        let dict  = numbered( 0..5 );
+        let alist = dict.to_alist();
+        let ( keys, vals ) = dict.into_columns();
+        assert_eq!( keys.len(), vals.len() );
+        for ( i, ( key, val ) ) in alist.into_iter().enumerate() {
+            assert_eq!( keys[i], key );
+            assert_eq!( vals[i], val );
+        }
+    }
+
+    #[test]
+    fn nearest_by_hash_picks_closest_on_ring() {
+        assert!( Dict::<i32>::new().nearest_by_hash( "x" ).is_none() );
+        let dict     = numbered( 0..5 );
+        let distance = |a : u64, b : u64| a.wrapping_sub( b ).min( b.wrapping_sub( a ) );
+        for i in 0..200 {
+            let query = format!( "q{}", i );
+            let hash  = hash_f( &query );
+            let best  = dict.iter().map( |o| distance( o.hash, hash ) ).min().unwrap();
+            let ( key, _ ) = dict.nearest_by_hash( &query ).unwrap();
+            assert_eq!( distance( hash_f( key ), hash ), best );
+        }
+        assert_eq!( dict.nearest_by_hash( "k3" ), Some( ( &"k3".to_string(), &3 ) ) );
+    }
+
+    #[test]
+    fn nearest_by_hash_tie_picks_preceding_element() {
+        let query = ( 0.. ).map( |i| format!( "q{}", i ) ).find( |k| hash_f( k ) > 1000 && hash_f( k ) < 1 << 62 ).unwrap();
+        let hash  = hash_f( &query );
+        let ring  = |gap : u64| -> Dict<&str> {
+            let mut dict = vec![ DictEntry{ hash: hash.wrapping_sub( gap ), key: "before".to_string(), val: "before" },
+                                 DictEntry{ hash: hash.wrapping_add( gap ), key: "after".to_string(),  val: "after"  } ];
+            dict.sort_by_hash();
+            dict
+        };
+        assert_eq!( ring( 5 ).nearest_by_hash( &query ).unwrap().1, &"before" );
+        // "before" wraps around to the end of the vector
+        let wrapped = ring( hash + 1000 );
+        assert_eq!( wrapped[1].key, "before" );
+        assert_eq!( wrapped.nearest_by_hash( &query ).unwrap().1, &"before" );
+    }
+
+    #[test]
+    fn key_fingerprints_are_sorted_hashes() {
+        let dict         = numbered( 0..9 );
+        let fingerprints = dict.key_fingerprints();
+        assert_eq!( fingerprints.len(), dict.len() );
+        assert!( fingerprints.windows( 2 ).all( |w| w[0] < w[1] ) );
+        for ( hash, o ) in fingerprints.iter().zip( dict.iter() ) {
+            assert_eq!( *hash, hash_of( &o.key ) );
+        }
+    }
+
+    #[test]
+    fn extract_where_partitions_dict() {
+        let mut dict  = numbered( 0..10 );
+        let extracted = dict.extract_where( |k, v| *v % 3 == 0 || k == "k1" );
+        assert_eq!( extracted.len(), 5 );
+        assert_eq!( dict.len(), 5 );
+        for o in &extracted {
+            assert!( o.val % 3 == 0 || o.key == "k1" );
+            assert!( !dict.contains_key( &o.key ) );
+        }
+        for o in &dict {
+            assert!( !extracted.contains_key( &o.key ) );
+        }
+        assert!( sorted_by_hash( &dict ) );
+        assert!( sorted_by_hash( &extracted ) );
+    }
+
+    #[test]
+    fn has_overlap_detects_shared_keys() {
+        let a     = numbered( 0..5 );
+        let mut b = numbered( 5..9 );
+        assert!( !a.has_overlap( &b ) );
+        b.add( "k2".to_string(), 0 );
+        assert!( a.has_overlap( &b ) );
+        assert!( b.has_overlap( &a ) );
+        assert!( !a.has_overlap( &Dict::new() ) );
+        assert!( !Dict::new().has_overlap( &a ) );
+    }
+
+    #[test]
+    fn to_table_aligns_values() {
+        let dict = dict_of( vec![ ( "port", 80 ), ( "a", 1 ), ( "ñandú", 3 ) ] );
+        assert_eq!( dict.to_table(), "Key    Value\na      1\nport   80\nñandú  3\n" );
+        assert_eq!( Dict::<i32>::new().to_table(), "Key  Value\n" );
+    }
+
+    #[test]
+    fn merge_add_sums_shared_keys() {
+        let mut counts = numbered( 0..6 );
+        let mut more   = Dict::new();
+        for i in 3..9 {
+            more.add( format!( "k{}", i ), 10 );
+        }
+        counts.merge_add( more );
+        assert_eq!( counts.len(), 9 );
+        assert_eq!( counts.get( "k1" ), Some( &1 ) );
+        assert_eq!( counts.get( "k4" ), Some( &14 ) );
+        assert_eq!( counts.get( "k8" ), Some( &10 ) );
+        assert!( sorted_by_hash( &counts ) );
+        let mut empty = Dict::new();
+        empty.merge_add( counts );
+        assert_eq!( empty.len(), 9 );
+    }
+
+    #[test]
+    fn is_hash_sorted_detects_corruption() {
+        assert!( Dict::<i32>::new().is_hash_sorted() );
+        let mut dict = numbered( 0..5 );
+        assert!( dict.is_hash_sorted() );
+        dict.swap( 1, 3 );
+        assert!( !dict.is_hash_sorted() );
+        // duplicated hashes are not strictly ascending either
+        let mut dup = numbered( 0..1 );
+        dup.push( dup[0].clone() );
+        assert!( !dup.is_hash_sorted() );
+    }
+
+    #[test]
+    fn remove_all_by_value_removes_every_match() {
+        let mut dict = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 1 ), ( "d", 3 ), ( "e", 1 ) ] );
+        assert!( dict.remove_all_by_value( &9 ).is_empty() );
+        assert_eq!( dict.len(), 5 );
+        assert_eq!( dict.remove_all_by_value( &2 ), vec![ ( "b".to_string(), 2 ) ] );
+        let mut removed = dict.remove_all_by_value( &1 );
+        removed.sort();
+        assert_eq!( removed, vec![ ( "a".to_string(), 1 ), ( "c".to_string(), 1 ), ( "e".to_string(), 1 ) ] );
+        assert_eq!( dict.len(), 1 );
+        assert_eq!( dict.get( "d" ), Some( &3 ) );
+    }
+
+    #[test]
+    fn invert_maps_values_to_keys() {
+        let dict     = dict_of( vec![ ( "a", 1 ), ( "b", 2 ), ( "c", 1 ) ] );
+        let inverted = dict.invert();
+        assert_eq!( inverted.len(), 2 );
+        assert_eq!( inverted[&2], vec![ "b".to_string() ] );
+        let mut ones = inverted[&1].clone();
+        ones.sort();
+        assert_eq!( ones, vec![ "a", "c" ] );
+    }
+
+    #[test]
+    fn join_yields_shared_keys_only() {
+        let left      = numbered( 0..6 );
+        let mut right = Dict::new();
+        for i in 4..9 {
+            right.add( format!( "k{}", i ), format!( "v{}", i ) );
+        }
+        let mut joined : Vec<(&String, &i32, &String)> = left.join( &right ).collect();
+        joined.sort();
+        assert_eq!( joined, vec![ ( &"k4".to_string(), &4, &"v4".to_string() ),
+                                  ( &"k5".to_string(), &5, &"v5".to_string() ) ] );
+        assert_eq!( left.join( &Dict::<u8>::new() ).count(), 0 );
+    }
+
+    #[test]
+    fn value_counts_sum_to_len() {
+        let dict   = dict_of( vec![ ( "a", "x" ), ( "b", "y" ), ( "c", "x" ), ( "d", "x" ) ] );
+        let counts = dict.value_counts();
+        assert_eq!( counts[&"x"], 3 );
+        assert_eq!( counts[&"y"], 1 );
+        assert_eq!( counts.values().sum::<usize>(), dict.len() );
+    }
+
+    #[test]
+    fn sorted_by_value_desc_then_key() {
+        let dict   = dict_of( vec![ ( "b", 1 ), ( "a", 1 ), ( "c", 5 ), ( "d", 3 ) ] );
+        let sorted : Vec<&str> = dict.sorted_by( |a, b| b.1.cmp( a.1 ).then( a.0.cmp( b.0 ) ) )
+            .into_iter()
+            .map( |( k, _ )| k.as_str() )
+            .collect();
+        assert_eq!( sorted, vec![ "c", "d", "a", "b" ] );
+    }
+
+    #[test]
+    fn symmetric_difference_keys_excludes_shared() {
+        let mut diff = numbered( 0..5 ).symmetric_difference_keys( &numbered( 3..7 ) );
+        diff.sort();
+        assert_eq!( diff, vec![ "k0", "k1", "k2", "k5", "k6" ] );
+        let dict = numbered( 0..5 );
+        assert!( dict.symmetric_difference_keys( &dict ).is_empty() );
+    }
+
+    #[test]
+    fn fill_defaults_never_overwrites() {
+        let mut dict = dict_of( vec![ ( "port", 8080 ) ] );
+        dict.fill_defaults( &dict_of( vec![ ( "port", 80 ), ( "host", 1 ), ( "timeout", 30 ) ] ) );
+        assert_eq!( dict.len(), 3 );
+        assert_eq!( dict.get( "port" ), Some( &8080 ) );
+        assert_eq!( dict.get( "host" ), Some( &1 ) );
+        assert_eq!( dict.get( "timeout" ), Some( &30 ) );
+        assert!( dict.is_hash_sorted() );
+    }
+
+    #[test]
+    fn blank_value_keys_finds_empty_and_whitespace() {
+        let dict = dict_of( vec![ ( "password", "" ), ( "user", " \t" ), ( "host", "h" ), ( "name", " x " ) ] );
+        let mut blank = dict.blank_value_keys();
+        blank.sort();
+        assert_eq!( blank, vec![ "password", "user" ] );
+    }
+
+    #[test]
+    fn hash_adjacent_pairs_follow_hash_order() {
+        assert_eq!( Dict::<i32>::new().hash_adjacent_pairs().count(), 0 );
+        assert_eq!( numbered( 0..1 ).hash_adjacent_pairs().count(), 0 );
+        let dict           = numbered( 0..5 );
+        let pairs : Vec<_> = dict.hash_adjacent_pairs().collect();
+        assert_eq!( pairs.len(), 4 );
+        for ( i, &( ( a, _ ), ( b, _ ) ) ) in pairs.iter().enumerate() {
+            assert_eq!( a, &dict[i].key );
+            assert_eq!( b, &dict[i + 1].key );
+        }
+    }
+
+    #[test]
+    fn average_hash_gap_needs_two_elements() {
+        assert!( Dict::<i32>::new().average_hash_gap().is_none() );
+        assert!( numbered( 0..1 ).average_hash_gap().is_none() );
+    }
+
+    #[test]
+    fn average_hash_gap_matches_mean_gap() {
+        let dict   = numbered( 0..100 );
+        let hashes = dict.key_fingerprints();
+        let mean   = hashes.windows( 2 ).map( |w| ( w[1] - w[0] ) as f64 ).sum::<f64>() / 99.0;
+        let gap    = dict.average_hash_gap().unwrap();
+        assert!( ( gap - mean ).abs() / mean < 1e-9 );
+        // well spread keys are close to 2^64 / len apart
+        let ideal = 2f64.powi( 64 ) / 100.0;
+        assert!( gap > ideal * 0.5 && gap < ideal * 1.5 );
+    }
+
+    #[test]
+    fn compare_and_swap_cases() {
+        let mut dict = dict_of( vec![ ( "a", 1 ) ] );
+        assert_eq!( dict.compare_and_swap( "a", &1, 2 ), Ok( 1 ) );
+        assert_eq!( dict.get( "a" ), Some( &2 ) );
+        assert_eq!( dict.compare_and_swap( "a", &1, 3 ), Err( Some( 2 ) ) );
+        assert_eq!( dict.get( "a" ), Some( &2 ) );
+        assert_eq!( dict.compare_and_swap( "b", &1, 3 ), Err( None ) );
+        assert!( !dict.contains_key( "b" ) );
+    }
+
+    #[test]
+    fn keys_where_filters_keys() {
+        let dict     = dict_of( vec![ ( "a", 0 ), ( "db.host", 0 ), ( "db.port", 0 ), ( "longer_key", 0 ) ] );
+        let mut long = dict.keys_where( |k| k.len() > 5 );
+        long.sort();
+        assert_eq!( long, vec![ "db.host", "db.port", "longer_key" ] );
+        let mut dotted = dict.keys_where( |k| k.contains( '.' ) );
+        dotted.sort();
+        assert_eq!( dotted, vec![ "db.host", "db.port" ] );
+    }
+
+    #[test]
+    fn stable_iter_follows_key_order() {
+        let dict     = dict_of( vec![ ( "b", 0 ), ( "A", 0 ), ( "a", 0 ), ( "é", 0 ), ( "z1", 0 ), ( "z", 0 ) ] );
+        let keys     : Vec<&String> = dict.stable_iter().map( |( k, _ )| k ).collect();
+        let mut want : Vec<&String> = dict.iter().map( |o| &o.key ).collect();
+        want.sort_by( |a, b| a.as_str().cmp( b.as_str() ) );
+        assert_eq!( keys, want );
+    }
+
+    #[test]
+    fn remove_where_counts_removed() {
+        let mut dict = numbered( 0..10 );
+        assert_eq!( dict.remove_where( |_, _| false ), 0 );
+        assert_eq!( dict.remove_where( |_, v| *v < 3 ), 3 );
+        assert_eq!( dict.len(), 7 );
+        assert!( !dict.contains_key( "k2" ) );
+        assert_eq!( dict.remove_where( |k, _| k.starts_with( 'k' ) ), 7 );
+        assert!( dict.is_empty() );
+    }
+
+    #[test]
+    fn get_parsed_cases() {
+        let dict = dict_of( vec![ ( "port", "8080".to_string() ), ( "bad", "x".to_string() ) ] );
+        assert_eq!( dict.get_parsed::<u16>( "port" ), Some( Ok( 8080 ) ) );
+        assert!( dict.get_parsed::<u16>( "nope" ).is_none() );
+        assert!( dict.get_parsed::<u16>( "bad" ).unwrap().is_err() );
+    }
+
+    #[test]
+    fn sorted_by_key_fn_natural_order() {
+        let dict    = dict_of( vec![ ( "item10", 0 ), ( "item2", 0 ), ( "item1", 0 ), ( "other", 0 ) ] );
+        let natural = |k : &str| {
+            let i = k.find( |c : char| c.is_ascii_digit() ).unwrap_or( k.len() );
+            ( k[..i].to_string(), k[i..].parse::<u64>().unwrap_or( 0 ) )
+        };
+        let keys : Vec<&str> = dict.sorted_by_key_fn( natural ).into_iter().map( |( k, _ )| k.as_str() ).collect();
+        assert_eq!( keys, vec![ "item1", "item2", "item10", "other" ] );
+    }
+
+    #[test]
+    fn drain_first_n_takes_up_to_n() {
+        let mut dict = numbered( 0..5 );
+        let first    = dict[0].key.clone();
+        let drained  = dict.drain_first_n( 2 );
+        assert_eq!( drained.len(), 2 );
+        assert_eq!( drained[0].0, first );
+        assert_eq!( dict.len(), 3 );
+        assert!( dict.is_hash_sorted() );
+        assert_eq!( dict.drain_first_n( 3 ).len(), 3 );
+        assert!( dict.is_empty() );
+        dict.add( "a".to_string(), 1 );
+        assert_eq!( dict.drain_first_n( 10 ).len(), 1 );
+        assert!( dict.drain_first_n( 1 ).is_empty() );
+    }
+
+    #[test]
+    fn reindex_by_value_last_wins() {
+        let dict     = dict_of( vec![ ( "x", ( 1, "a" ) ), ( "y", ( 2, "b" ) ), ( "z", ( 1, "c" ) ) ] );
+        let reindex  = dict.reindex_by_value( |v| v.0.to_string() );
+        let last_one = dict.iter().rfind( |o| o.val.0 == 1 ).unwrap().val;
+        assert_eq!( reindex.len(), 2 );
+        assert_eq!( reindex.get( "2" ), Some( &( 2, "b" ) ) );
+        assert_eq!( reindex.get( "1" ), Some( &last_one ) );
+    }
+
+    #[test]
+    fn query_string_round_trip() {
+        let dict  = dict_of( vec![ ( "q", "a b&c=d".to_string() ), ( "ñ key", "100%".to_string() ), ( "plain", "x-y_z.~".to_string() ) ] );
+        let query = dict.to_query_string();
+        assert_eq!( query, "plain=x-y_z.~&q=a%20b%26c%3Dd&%C3%B1%20key=100%25" );
+        let back  = Dict::<String>::from_query_string( &query ).unwrap();
+        assert!( back.matches_hashmap( &dict.iter().map( |o| ( o.key.clone(), o.val.clone() ) ).collect() ) );
+        let plus  = Dict::<String>::from_query_string( "a+b=c+d&flag&&" ).unwrap();
+        assert_eq!( plus.get( "a b" ).map( |s| s.as_str() ), Some( "c d" ) );
+        assert_eq!( Dict::<String>::from_query_string( "" ).unwrap().len(), 0 );
+    }
+
+    #[test]
+    fn query_string_rejects_malformed() {
+        assert!( Dict::<String>::from_query_string( "a=%zz" ).is_none() );
+        assert!( Dict::<String>::from_query_string( "a=%4" ).is_none() );
+        assert!( Dict::<String>::from_query_string( "a=%+1" ).is_none() );
+        assert!( Dict::<u8>::from_query_string( "a=300" ).is_none() );
+    }
+
+    #[test]
+    fn add_namespaced_prefixes_keys() {
+        let db       = dict_of( vec![ ( "host", 1 ), ( "port", 2 ) ] );
+        let cache    = dict_of( vec![ ( "host", 3 ), ( "port", 4 ) ] );
+        let mut dict = dict_of( vec![ ( "db.host", 0 ) ] );
+        dict.add_namespaced( "db", &db );
+        dict.add_namespaced( "cache", &cache );
+        assert_eq!( dict.len(), 4 );
+        assert!( dict.is_hash_sorted() );
+        assert_eq!( dict.get( "db.host" ), Some( &1 ) );
+        assert_eq!( dict.get( "db.port" ), Some( &2 ) );
+        assert_eq!( dict.get( "cache.host" ), Some( &3 ) );
+        assert_eq!( dict.get( "cache.port" ), Some( &4 ) );
+        assert!( !dict.contains_key( "host" ) );
+    }
+
+    #[test]
+    fn validate_all_collects_every_error() {
+        let dict       = dict_of( vec![ ( "a", 1 ), ( "b", -2 ), ( "c", 3 ), ( "d", -4 ) ] );
+        let positive   = |_ : &str, v : &i32| if *v > 0 { Ok( () ) } else { Err( format!( "{} is negative", v ) ) };
+        let mut errors = dict.validate_all( positive ).err().unwrap();
+        errors.sort();
+        assert_eq!( errors, vec![ ( "b".to_string(), "-2 is negative".to_string() ), ( "d".to_string(), "-4 is negative".to_string() ) ] );
+        assert_eq!( dict.validate_all( |_, _| Ok( () ) ), Ok( () ) );
+    }
+
+    #[test]
+    fn patch_round_trip() {
+        let mut dict = numbered( 0..6 );
+        let mut dest = numbered( 3..9 );
+        *dest.get_mut( "k4" ).unwrap() = 40;
+        let patch = dict.make_patch( &dest );
+        assert_eq!( patch.len(), 7 );
+        assert!( patch.contains( &PatchOp::Update( "k4".to_string(), 40 ) ) );
+        assert!( patch.contains( &PatchOp::Delete( "k0".to_string() ) ) );
+        assert!( patch.contains( &PatchOp::Insert( "k8".to_string(), 8 ) ) );
+        dict.apply_patch( patch );
+        assert!( dict.matches_hashmap( &dest.iter().map( |o| ( o.key.clone(), o.val ) ).collect() ) );
+        assert!( dict.make_patch( &dest ).is_empty() );
+        let patch = dict.make_patch( &Dict::new() );
+        dict.apply_patch( patch );
+        assert!( dict.is_empty() );
+    }
+
+    #[test]
+    fn write_ndjson_escapes_keys_and_values() {
+        let dict    = dict_of( vec![ ( "b", 2 ), ( "say \"hi\"\\", 1 ), ( "a\n", 3 ) ] );
+        let mut buf = Vec::new();
+        dict.write_ndjson( &mut buf ).unwrap();
+        let out     = String::from_utf8( buf ).unwrap();
+        assert_eq!( out.lines().count(), 3 );
+        assert_eq!( out, "{\"key\":\"a\\n\",\"val\":\"3\"}\n{\"key\":\"b\",\"val\":\"2\"}\n{\"key\":\"say \\\"hi\\\"\\\\\",\"val\":\"1\"}\n" );
+    }
+
+    #[test]
+    fn write_ndjson_quotes_string_values() {
+        let dict    = dict_of( vec![ ( "name", "bob \"b\"".to_string() ) ] );
+        let mut buf = Vec::new();
+        dict.write_ndjson( &mut buf ).unwrap();
+        assert_eq!( String::from_utf8( buf ).unwrap(), "{\"key\":\"name\",\"val\":\"bob \\\"b\\\"\"}\n" );
+    }
+
+    #[test]
+    fn value_statistics_summarizes_values() {
+        assert!( Dict::<u32>::new().value_statistics().is_none() );
+        let dict = dict_of( vec![ ( "a", 4u32 ), ( "b", 1 ), ( "c", 7 ), ( "d", 0 ) ] );
+        assert_eq!( dict.value_statistics(), Some( ValueStats{ count: 4, sum: 12.0, mean: 3.0, min: 0.0, max: 7.0 } ) );
+        let single = dict_of( vec![ ( "x", -1.5f32 ) ] );
+        assert_eq!( single.value_statistics().unwrap().max, -1.5 );
+    }
+
+    #[test]
+    fn dedup_by_value_keeps_first_in_hash_order() {
+        let mut dict = dict_of( vec![ ( "a", "x" ), ( "b", "y" ), ( "c", "x" ), ( "d", "x" ), ( "e", "y" ), ( "f", "z" ) ] );
+        let first_x  = dict.iter().find( |o| o.val == "x" ).unwrap().key.clone();
+        dict.dedup_by_value();
+        assert_eq!( dict.len(), 3 );
+        assert!( dict.is_hash_sorted() );
+        assert_eq!( dict.get( &first_x ), Some( &"x" ) );
+        let mut vals : Vec<&str> = dict.iter().map( |o| o.val ).collect();
+        vals.sort();
+        assert_eq!( vals, vec![ "x", "y", "z" ] );
+    }
+
+    #[test]
+    fn get_with_fallback_most_specific_wins() {
+        let dict = dict_of( vec![ ( "*", 0 ), ( "a.*", 1 ), ( "a.b.*", 2 ), ( "a.b.c", 3 ) ] );
+        assert_eq!( dict.get_with_fallback( "a.b.c", '.' ), Some( &3 ) );
+        assert_eq!( dict.get_with_fallback( "a.b.d", '.' ), Some( &2 ) );
+        assert_eq!( dict.get_with_fallback( "a.x.y", '.' ), Some( &1 ) );
+        assert_eq!( dict.get_with_fallback( "z", '.' ), Some( &0 ) );
+        assert_eq!( dict.get_with_fallback( "q.r", '.' ), Some( &0 ) );
+        assert_eq!( dict.get_with_fallback( "a/b", '/' ), Some( &0 ) );
+        assert!( Dict::<i32>::new().get_with_fallback( "a.b", '.' ).is_none() );
+    }
+}
+
 // License
 //
 // This script is free software; you can redistribute it and/or modify it
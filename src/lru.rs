@@ -0,0 +1,120 @@
+//!
+//! Bounded dictionary evicting the least recently used element.
+//!
+
+use { Dict, DictIface, debug_check_invariant };
+
+/// Dictionary holding at most a fixed number of elements. When full, adding a new key first
+/// evicts the element that was added or read the longest time ago.
+pub struct LruDict<T> { entries : Dict<(T, u64)>, capacity : usize, tick : u64 }
+
+impl<T> LruDict<T> {
+    /// Create an empty dictionary holding up to _capacity_ elements.
+    pub fn new( capacity : usize ) -> LruDict<T> {
+        LruDict{ entries: Dict::with_capacity( capacity ), capacity, tick: 0 }
+    }
+    /// Add an element _val_ of type T, indexed by the string _key_, evicting the least recently
+    /// used element if full. Returns false if the key exists, there is a hash collision or the
+    /// capacity is 0
+    pub fn add( &mut self, key : String, val : T ) -> bool {
+        if self.capacity == 0 || self.entries.contains_key( &key ) {
+            return false;
+        }
+        if self.entries.len() >= self.capacity {
+            self.evict();
+        }
+        let tick = self.next_tick();
+        self.entries.add( key, ( val, tick ) )
+    }
+    /// Return a reference to the value identified by the key _key_, if exists, and mark it as
+    /// the most recently used.
+    pub fn get( &mut self, key : &str ) -> Option<&T> {
+        let tick = self.next_tick();
+        self.entries.get_mut( key ).map( |o| {
+            o.1 = tick;
+            &o.0
+        } )
+    }
+    /// Return a reference to the value identified by the key _key_, if exists, without marking
+    /// it as used.
+    pub fn peek( &self, key : &str ) -> Option<&T> {
+        self.entries.get( key ).map( |o| &o.0 )
+    }
+    /// Return true if an element identified by the key _key_ exists.
+    pub fn contains_key( &self, key : &str ) -> bool {
+        self.entries.contains_key( key )
+    }
+    /// Remove the element identified by the key _key_ and return it, if exists.
+    pub fn remove_key( &mut self, key : &str ) -> Option<T> {
+        self.entries.remove_key( key ).map( |o| o.0 )
+    }
+    /// Return the number of elements.
+    pub fn len( &self ) -> usize {
+        self.entries.len()
+    }
+    /// Return true if there are no elements.
+    pub fn is_empty( &self ) -> bool {
+        self.entries.is_empty()
+    }
+    /// Return the maximum number of elements.
+    pub fn capacity( &self ) -> usize {
+        self.capacity
+    }
+
+    fn next_tick( &mut self ) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn evict( &mut self ) {
+        let oldest = self.entries.iter().enumerate().min_by_key( |&( _, o )| o.val.1 ).map( |( pos, _ )| pos );
+        if let Some( pos ) = oldest {
+            self.entries.remove( pos );
+            debug_check_invariant( &self.entries );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_added() {
+        let mut dict = LruDict::<i32>::new( 2 );
+        assert!( dict.add( "a".to_string(), 1 ) );
+        assert!( dict.add( "b".to_string(), 2 ) );
+        assert!( dict.add( "c".to_string(), 3 ) );
+        assert_eq!( dict.len(), 2 );
+        assert!( !dict.contains_key( "a" ) );
+        assert!( dict.contains_key( "b" ) );
+        assert!( dict.contains_key( "c" ) );
+    }
+
+    #[test]
+    fn get_bumps_recency_and_peek_does_not() {
+        let mut dict = LruDict::<i32>::new( 2 );
+        dict.add( "a".to_string(), 1 );
+        dict.add( "b".to_string(), 2 );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+        assert!( dict.add( "c".to_string(), 3 ) );
+        assert!( !dict.contains_key( "b" ) );
+        assert_eq!( dict.peek( "a" ), Some( &1 ) );
+        assert!( dict.add( "d".to_string(), 4 ) );
+        assert!( !dict.contains_key( "a" ) );
+        assert!( dict.contains_key( "c" ) );
+    }
+
+    #[test]
+    fn rejects_duplicates_and_zero_capacity() {
+        let mut dict = LruDict::<i32>::new( 2 );
+        dict.add( "a".to_string(), 1 );
+        assert!( !dict.add( "a".to_string(), 9 ) );
+        assert_eq!( dict.peek( "a" ), Some( &1 ) );
+        assert_eq!( dict.remove_key( "a" ), Some( 1 ) );
+        assert!( dict.is_empty() );
+        let mut none = LruDict::<i32>::new( 0 );
+        assert!( !none.add( "a".to_string(), 1 ) );
+        assert_eq!( none.capacity(), 0 );
+    }
+}
@@ -0,0 +1,51 @@
+//!
+//! Linear walk over two dictionaries at once, relying on both being sorted by hash.
+//!
+
+use DictEntry;
+
+/// Element visited by a MergeWalk.
+pub(crate) enum Merged<'a, T, U> {
+    /// Key only present on the left side.
+    Left ( &'a DictEntry<T> ),
+    /// Key only present on the right side.
+    Right( &'a DictEntry<U> ),
+    /// Key present on both sides.
+    Both ( &'a DictEntry<T>, &'a DictEntry<U> ),
+}
+
+/// Iterator visiting the elements of two dictionaries in hash order. Elements with equal hashes
+/// but different keys are visited as Left and then Right.
+pub(crate) struct MergeWalk<'a, T, U> { left : &'a [DictEntry<T>], right : &'a [DictEntry<U>] }
+
+impl<'a, T, U> MergeWalk<'a, T, U> {
+    pub(crate) fn new( left : &'a [DictEntry<T>], right : &'a [DictEntry<U>] ) -> MergeWalk<'a, T, U> {
+        MergeWalk{ left, right }
+    }
+}
+
+impl<'a, T, U> Iterator for MergeWalk<'a, T, U> {
+    type Item = Merged<'a, T, U>;
+
+    fn next( &mut self ) -> Option<Merged<'a, T, U>> {
+        use std::cmp::Ordering::*;
+        let ( left, right ) = ( self.left, self.right );
+        let merged = match ( left.first(), right.first() ) {
+            ( None   , None    ) => return None,
+            ( Some(a), None    ) => Merged::Left( a ),
+            ( None   , Some(b) ) => Merged::Right( b ),
+            ( Some(a), Some(b) ) => match a.hash.cmp( &b.hash ) {
+                Less                    => Merged::Left( a ),
+                Greater                 => Merged::Right( b ),
+                Equal if a.key == b.key => Merged::Both( a, b ),
+                Equal                   => Merged::Left( a ),   // hash collision
+            },
+        };
+        match merged {
+            Merged::Left ( _ )    => self.left  = &left[1..],
+            Merged::Right( _ )    => self.right = &right[1..],
+            Merged::Both ( _, _ ) => { self.left = &left[1..]; self.right = &right[1..] },
+        }
+        Some( merged )
+    }
+}
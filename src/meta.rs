@@ -0,0 +1,193 @@
+//!
+//! Dictionaries carrying per-element metadata.
+//!
+//! A MetaDict works just like a Dict, but each element also holds a value of type M, such as an
+//! insertion time, that can be queried separately from the value itself.
+//!
+//! A TimedDict is a MetaDict holding the insertion time of each element, which can be used to
+//! implement simple caches.
+//!
+//! A VersionedDict is a MetaDict holding a version number for each element, to merge replicas
+//! keeping the newest value of each key.
+//!
+
+use std::time::Instant;
+
+use { debug_check_hashes, hash_f };
+
+pub struct MetaDictEntry<T, M> { hash : u64, pub key : String, pub val : T, pub meta : M }
+
+pub type MetaDict<T, M> = Vec<MetaDictEntry<T, M>>;
+
+pub trait MetaDictIface<T, M> {
+    fn add( &mut self, key : String, val : T, meta : M ) -> bool;
+    fn get( &self, key : &str ) -> Option<&T>;
+    fn get_meta( &self, key : &str ) -> Option<&M>;
+    fn contains_key( &self, key : &str ) -> bool;
+    fn remove_key( &mut self, key : &str ) -> Option<(T, M)>;
+}
+
+impl<T, M> MetaDictIface<T, M> for MetaDict<T, M> {
+    /// Add an element _val_ of type T with metadata _meta_, indexed by the string _key_. Returns
+    /// false if the key exists or there is a hash collision
+    fn add( &mut self, key : String, val : T, meta : M ) -> bool {
+        match self.binary_search_by_key( &hash_f(&key), |o| o.hash ) {
+            Ok (  _  ) => return false,   // key exists or hash collision
+            Err( pos ) => self.insert( pos, MetaDictEntry{ hash: hash_f( &key ), key, val, meta } ),
+        }
+        debug_check_hashes( self.iter().map( |o| o.hash ) );
+        true
+    }
+    /// Return a reference to the value identified by the key _key_, if exists.
+    fn get( &self, key : &str ) -> Option<&T> {
+        if let Ok( pos ) = self.binary_search_by_key( &hash_f(key), |o| o.hash ) {
+            Some( &self[pos].val )
+        } else { None }
+    }
+    /// Return a reference to the metadata of the element identified by the key _key_, if exists.
+    fn get_meta( &self, key : &str ) -> Option<&M> {
+        if let Ok( pos ) = self.binary_search_by_key( &hash_f(key), |o| o.hash ) {
+            Some( &self[pos].meta )
+        } else { None }
+    }
+    /// Return true if an element identified by the key _key_ exists.
+    fn contains_key( &self, key : &str ) -> bool {
+        self.get( key ).is_some()
+    }
+    /// Remove the element identified by the key _key_ and return its value and metadata, if
+    /// exists.
+    fn remove_key( &mut self, key : &str ) -> Option<(T, M)> {
+        if let Ok( pos ) = self.binary_search_by_key( &hash_f(key), |o| o.hash ) {
+            let entry = self.remove( pos );
+            debug_check_hashes( self.iter().map( |o| o.hash ) );
+            Some( ( entry.val, entry.meta ) )
+        } else { None }
+    }
+}
+
+pub type TimedDict<T> = MetaDict<T, Instant>;
+
+pub trait TimedDictIface<T> {
+    fn add_now( &mut self, key : String, val : T ) -> bool;
+    fn expire_before( &mut self, cutoff : Instant ) -> usize;
+}
+
+impl<T> TimedDictIface<T> for TimedDict<T> {
+    /// Add an element _val_ of type T stamped with the current time, indexed by the string _key_.
+    /// Returns false if the key exists or there is a hash collision
+    fn add_now( &mut self, key : String, val : T ) -> bool {
+        self.add( key, val, Instant::now() )
+    }
+    /// Remove all the elements inserted before _cutoff_, and return how many were removed.
+    fn expire_before( &mut self, cutoff : Instant ) -> usize {
+        let len = self.len();
+        self.retain( |o| o.meta >= cutoff );
+        debug_check_hashes( self.iter().map( |o| o.hash ) );
+        len - self.len()
+    }
+}
+
+pub type VersionedDict<T> = MetaDict<T, u64>;
+
+pub trait VersionedDictIface<T> {
+    fn merge_versioned( &mut self, other : VersionedDict<T> );
+}
+
+impl<T> VersionedDictIface<T> for VersionedDict<T> {
+    /// Merge _other_ in a single linear pass, keeping for each key the element with the higher
+    /// version. On equal versions the existing element is kept. Like add(), elements of _other_
+    /// whose hash collides with a different key are dropped.
+    fn merge_versioned( &mut self, other : VersionedDict<T> ) {
+        let mut old = ::std::mem::take( self ).into_iter().peekable();
+        self.reserve( old.len() + other.len() );
+        for o in other {
+            while let Some( e ) = old.next_if( |e| e.hash < o.hash ) {
+                self.push( e );
+            }
+            match old.next_if( |e| e.hash == o.hash ) {
+                Some( e ) => self.push( if e.key == o.key && o.meta > e.meta { o } else { e } ),
+                None      => self.push( o ),
+            }
+        }
+        self.extend( old );
+        debug_check_hashes( self.iter().map( |o| o.hash ) );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versioned( elems : &[(&str, i32, u64)] ) -> VersionedDict<i32> {
+        let mut dict = VersionedDict::new();
+        for &( key, val, version ) in elems {
+            dict.add( key.to_string(), val, version );
+        }
+        dict
+    }
+
+    #[test]
+    fn metadata_is_stored_alongside_values() {
+        let mut dict = MetaDict::<i32, &str>::new();
+        assert!( dict.add( "a".to_string(), 1, "first" ) );
+        assert!( !dict.add( "a".to_string(), 2, "second" ) );
+        assert!( dict.add( "b".to_string(), 2, "other" ) );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+        assert_eq!( dict.get_meta( "a" ), Some( &"first" ) );
+        assert_eq!( dict.get_meta( "c" ), None );
+        assert_eq!( dict.remove_key( "a" ), Some( ( 1, "first" ) ) );
+        assert!( !dict.contains_key( "a" ) );
+        assert!( dict.contains_key( "b" ) );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invariant violated")]
+    fn add_panics_on_corrupted_order() {
+        let mut dict = MetaDict::<i32, ()>::new();
+        for key in &[ "a", "b", "c" ] {
+            dict.add( key.to_string(), 0, () );
+        }
+        dict.reverse();
+        dict.add( "d".to_string(), 0, () );
+    }
+
+    #[test]
+    fn expire_before_removes_older_entries() {
+        use std::time::Duration;
+
+        let start    = Instant::now();
+        let mut dict = TimedDict::<i32>::new();
+        dict.add( "old1".to_string(), 1, start );
+        dict.add( "old2".to_string(), 2, start + Duration::from_secs( 1 ) );
+        dict.add( "new".to_string(), 3, start + Duration::from_secs( 10 ) );
+        assert!( dict.add_now( "now".to_string(), 4 ) );
+        assert_eq!( dict.expire_before( start + Duration::from_secs( 5 ) ), 3 );
+        assert_eq!( dict.len(), 1 );
+        assert!( dict.contains_key( "new" ) );
+        assert_eq!( dict.expire_before( start ), 0 );
+    }
+
+    #[test]
+    fn merge_versioned_keeps_newest_in_both_directions() {
+        let a = [ ( "a", 1, 1 ), ( "b", 2, 5 ), ( "c", 3, 1 ) ];
+        let b = [ ( "a", 10, 2 ), ( "b", 20, 4 ), ( "d", 40, 1 ) ];
+        for &( x, y ) in &[ ( &a[..], &b[..] ), ( &b[..], &a[..] ) ] {
+            let mut dict = versioned( x );
+            dict.merge_versioned( versioned( y ) );
+            assert_eq!( dict.len(), 4 );
+            assert_eq!( dict.get( "a" ), Some( &10 ) );
+            assert_eq!( dict.get_meta( "a" ), Some( &2 ) );
+            assert_eq!( dict.get( "b" ), Some( &2 ) );
+            assert_eq!( dict.get( "c" ), Some( &3 ) );
+            assert_eq!( dict.get( "d" ), Some( &40 ) );
+        }
+    }
+
+    #[test]
+    fn merge_versioned_keeps_own_value_on_tie() {
+        let mut dict = versioned( &[ ( "a", 1, 1 ) ] );
+        dict.merge_versioned( versioned( &[ ( "a", 2, 1 ) ] ) );
+        assert_eq!( dict.get( "a" ), Some( &1 ) );
+    }
+}
@@ -0,0 +1,17 @@
+//!
+//! Changes turning a dictionary into another one.
+//!
+
+/// Single change of a Patch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchOp<T> {
+    /// Add the key with the value.
+    Insert( String, T ),
+    /// Replace the value of an existing key.
+    Update( String, T ),
+    /// Remove the key.
+    Delete( String ),
+}
+
+/// Changes returned by DictIface::make_patch(), in hash order.
+pub type Patch<T> = Vec<PatchOp<T>>;
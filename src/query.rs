@@ -0,0 +1,56 @@
+//!
+//! Result of a Dict query.
+//!
+
+use DictEntry;
+
+/// Elements of a Dict matched by a query, in hash order.
+pub struct QueryResult<'a, T> { entries : Vec<&'a DictEntry<T>> }
+
+impl<'a, T> QueryResult<'a, T> {
+    pub(crate) fn new( entries : Vec<&'a DictEntry<T>> ) -> QueryResult<'a, T> {
+        QueryResult{ entries }
+    }
+    /// Return the number of matched elements.
+    pub fn count( &self ) -> usize {
+        self.entries.len()
+    }
+    /// Return true if no element matched.
+    pub fn is_empty( &self ) -> bool {
+        self.entries.is_empty()
+    }
+    /// Return the matched elements.
+    pub fn entries( &self ) -> &[&'a DictEntry<T>] {
+        &self.entries
+    }
+    /// Iterate over the keys of the matched elements.
+    pub fn keys( &self ) -> impl Iterator<Item = &'a String> + '_ {
+        self.entries.iter().map( |o| &o.key )
+    }
+    /// Iterate over the values of the matched elements.
+    pub fn values( &self ) -> impl Iterator<Item = &'a T> + '_ {
+        self.entries.iter().map( |o| &o.val )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use { Dict, DictIface };
+
+    #[test]
+    fn projections_match_the_match_set() {
+        let mut dict = Dict::<i32>::new();
+        for i in 0..10 {
+            dict.add( format!( "k{}", i ), i );
+        }
+        let result = dict.query( |k, v| *v > 5 || k == "k0" );
+        assert_eq!( result.count(), 5 );
+        assert_eq!( result.entries().len(), 5 );
+        assert_eq!( result.keys().count(), 5 );
+        for ( key, val ) in result.keys().zip( result.values() ) {
+            assert_eq!( dict.get( key ), Some( val ) );
+            assert!( *val > 5 || key == "k0" );
+        }
+        assert!( dict.query( |_, _| false ).is_empty() );
+    }
+}
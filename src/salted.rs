@@ -0,0 +1,104 @@
+//!
+//! Dictionaries hashing their keys with a salt.
+//!
+
+use { DictEntry, debug_check_hashes, hash_f };
+
+/// Dictionary mixing a salt into every key hash. Without it, key hashes are the same on every
+/// run, so untrusted input could be crafted to collide. Pick a random salt, e.g. at startup, so
+/// that collisions can not be predicted.
+pub struct SaltedDict<T> { salt : u64, entries : Vec<DictEntry<T>> }
+
+impl<T> SaltedDict<T> {
+    /// Create an empty dictionary hashing its keys with _salt_.
+    pub fn with_salt( salt : u64 ) -> SaltedDict<T> {
+        SaltedDict{ salt, entries: Vec::new() }
+    }
+    /// Return the salt.
+    pub fn salt( &self ) -> u64 {
+        self.salt
+    }
+    /// Return the salted hash under which _key_ is stored.
+    pub fn hash_of( &self, key : &str ) -> u64 {
+        hash_f( ( self.salt, key ) )
+    }
+    /// Add an element _val_ of type T, indexed by the string _key_. Returns false if the key
+    /// exists or there is a hash collision
+    pub fn add( &mut self, key : String, val : T ) -> bool {
+        let hash = self.hash_of( &key );
+        match self.entries.binary_search_by_key( &hash, |o| o.hash ) {
+            Ok (  _  ) => return false,   // key exists or hash collision
+            Err( pos ) => self.entries.insert( pos, DictEntry{ hash, key, val } ),
+        }
+        debug_check_hashes( self.entries.iter().map( |o| o.hash ) );
+        true
+    }
+    /// Remove the element identified by the key _key_ and return it, if exists.
+    pub fn remove_key( &mut self, key : &str ) -> Option<T> {
+        let val = self.position( key ).map( |pos| self.entries.remove( pos ).val );
+        debug_check_hashes( self.entries.iter().map( |o| o.hash ) );
+        val
+    }
+    /// Return a reference to the value identified by the key _key_, if exists.
+    pub fn get( &self, key : &str ) -> Option<&T> {
+        self.position( key ).map( |pos| &self.entries[pos].val )
+    }
+    /// Return true if an element identified by the key _key_ exists.
+    pub fn contains_key( &self, key : &str ) -> bool {
+        self.position( key ).is_some()
+    }
+    /// Return the number of elements.
+    pub fn len( &self ) -> usize {
+        self.entries.len()
+    }
+    /// Return true if there are no elements.
+    pub fn is_empty( &self ) -> bool {
+        self.entries.is_empty()
+    }
+    /// Iterate over the elements in salted hash order.
+    pub fn iter( &self ) -> ::std::slice::Iter<'_, DictEntry<T>> {
+        self.entries.iter()
+    }
+
+    fn position( &self, key : &str ) -> Option<usize> {
+        self.entries.binary_search_by_key( &self.hash_of( key ), |o| o.hash ).ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SaltedDict<T> {
+    type Item     = &'a DictEntry<T>;
+    type IntoIter = ::std::slice::Iter<'a, DictEntry<T>>;
+    fn into_iter( self ) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salts_change_stored_hashes() {
+        let a = SaltedDict::<i32>::with_salt( 1 );
+        let b = SaltedDict::<i32>::with_salt( 2 );
+        assert_eq!( a.salt(), 1 );
+        assert_ne!( a.hash_of( "key" ), b.hash_of( "key" ) );
+        assert_ne!( a.hash_of( "key" ), hash_f( "key" ) );
+    }
+
+    #[test]
+    fn lookups_apply_the_salt() {
+        let mut dict = SaltedDict::<i32>::with_salt( 42 );
+        for i in 0..20 {
+            assert!( dict.add( format!( "k{}", i ), i ) );
+        }
+        for i in 0..20 {
+            assert_eq!( dict.get( &format!( "k{}", i ) ), Some( &i ) );
+        }
+        assert!( !dict.add( "k3".to_string(), 0 ) );
+        assert_eq!( dict.remove_key( "k3" ), Some( 3 ) );
+        assert!( !dict.contains_key( "k3" ) );
+        assert_eq!( dict.len(), 19 );
+        assert!( dict.iter().zip( dict.iter().skip( 1 ) ).all( |( x, y )| x.hash < y.hash ) );
+    }
+}
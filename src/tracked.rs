@@ -0,0 +1,98 @@
+//!
+//! Dictionary wrapper that tracks its peak size.
+//!
+
+use { Dict, DictIface };
+
+/// Dict wrapper that remembers the largest number of elements it ever held, for capacity
+/// planning.
+pub struct TrackedDict<T> { dict : Dict<T>, high_water_mark : usize }
+
+impl<T> TrackedDict<T> {
+    /// Create an empty dictionary.
+    pub fn new() -> TrackedDict<T> {
+        TrackedDict{ dict: Dict::new(), high_water_mark: 0 }
+    }
+    /// Add an element _val_ of type T, indexed by the string _key_. Returns false if the key
+    /// exists or there is a hash collision
+    pub fn add( &mut self, key : String, val : T ) -> bool {
+        let added = self.dict.add( key, val );
+        self.high_water_mark = self.high_water_mark.max( self.dict.len() );
+        added
+    }
+    /// Remove the element identified by the key _key_ and return it, if exists.
+    pub fn remove_key( &mut self, key : &str ) -> Option<T> {
+        self.dict.remove_key( key )
+    }
+    /// Return a reference to the value identified by the key _key_, if exists.
+    pub fn get( &self, key : &str ) -> Option<&T> {
+        self.dict.get( key )
+    }
+    /// Return the number of elements.
+    pub fn len( &self ) -> usize {
+        self.dict.len()
+    }
+    /// Return true if there are no elements.
+    pub fn is_empty( &self ) -> bool {
+        self.dict.is_empty()
+    }
+    /// Return the largest number of elements held so far.
+    pub fn high_water_mark( &self ) -> usize {
+        self.high_water_mark
+    }
+    /// Reserve capacity for exactly _additional_ more elements.
+    pub fn reserve_exact_entries( &mut self, additional : usize ) {
+        self.dict.reserve_exact_entries( additional );
+    }
+    /// Return a reference to the underlying dictionary.
+    pub fn dict( &self ) -> &Dict<T> {
+        &self.dict
+    }
+    /// Consume the wrapper and return the underlying dictionary.
+    pub fn into_dict( self ) -> Dict<T> {
+        self.dict
+    }
+}
+
+impl<T> Default for TrackedDict<T> {
+    fn default() -> TrackedDict<T> {
+        TrackedDict::new()
+    }
+}
+
+impl<T> From<Dict<T>> for TrackedDict<T> {
+    fn from( dict : Dict<T> ) -> TrackedDict<T> {
+        let high_water_mark = dict.len();
+        TrackedDict{ dict, high_water_mark }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_water_mark_stays_at_peak() {
+        let mut dict = TrackedDict::<i32>::new();
+        assert_eq!( dict.high_water_mark(), 0 );
+        for i in 0..5 {
+            dict.add( format!( "k{}", i ), i );
+        }
+        for i in 0..4 {
+            dict.remove_key( &format!( "k{}", i ) );
+        }
+        dict.add( "x".to_string(), 1 );
+        assert_eq!( dict.len(), 2 );
+        assert_eq!( dict.high_water_mark(), 5 );
+    }
+
+    #[test]
+    fn reserve_exact_entries_grows_capacity() {
+        let mut dict = TrackedDict::<i32>::new();
+        dict.reserve_exact_entries( 10 );
+        assert!( dict.dict().capacity() >= 10 );
+        let mut plain = Dict::<i32>::new();
+        plain.reserve_exact_entries( 3 );
+        assert!( plain.capacity() >= 3 );
+    }
+}